@@ -0,0 +1,122 @@
+#![cfg_attr(target_arch = "wasm32", allow(unused))]
+
+//! Builds an opening book from finished games.
+//!
+//! Usage: `ccheckers_book [--moves N] --out FILE GAMES...`
+//!
+//! Every input file is either a saved game or the records written by `ccheckers_tournament
+//! --records`, one game per line. Only the first N moves of every game, 12 unless given, go into
+//! the book, and the moves of a game's winner count twice. Saved games that do not start from the
+//! standard position are left out.
+
+use ccheckers_wasm::{
+    board::{Board, Move, Outcome},
+    book::{self, Book},
+    player::Mode,
+};
+use serde::Deserialize;
+
+/// The parts of a tournament record a book is built from
+#[derive(Deserialize)]
+struct Record {
+    mode: Mode,
+    outcome: Option<Outcome>,
+    #[serde(default)]
+    history: Vec<Move>,
+}
+
+struct Config {
+    moves: usize,
+    out: String,
+    games: Vec<String>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Config, String> {
+    let mut moves = book::DEFAULT_MOVES;
+    let mut out = None;
+    let mut games = Vec::new();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value for {arg}"))
+        };
+        match arg.as_str() {
+            "--moves" => moves = value()?.parse().map_err(|e| format!("{e}"))?,
+            "--out" => out = Some(value()?),
+            _ if arg.starts_with("--") => return Err(format!("unknown argument {arg}")),
+            _ => games.push(arg),
+        }
+    }
+    if games.is_empty() {
+        return Err("at least one file of games must be given".to_string());
+    }
+    Ok(Config {
+        moves,
+        out: out.ok_or_else(|| "--out must be given".to_string())?,
+        games,
+    })
+}
+
+/// Adds every game of the file to the book, returning how many there were
+fn add_file(book: &mut Book, file: &str) -> Result<usize, String> {
+    let data = std::fs::read_to_string(file).map_err(|e| format!("could not read {file}: {e}"))?;
+    if let Ok(board) = ron::from_str::<Board>(&data) {
+        let mut start = Board::default();
+        start.reset(board.mode);
+        if board.replay()[0].hash() != start.hash() {
+            eprintln!(
+                "warning: {file}: skipped, the game does not start from the standard position"
+            );
+            return Ok(0);
+        }
+        let winner = board.outcome().and_then(|outcome| outcome.winner());
+        book.add_game(board.mode, &board.history, winner)
+            .map_err(|e| format!("{file}: {e}"))?;
+        return Ok(1);
+    }
+
+    let mut games = 0;
+    for (i, line) in data.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: Record =
+            ron::from_str(line).map_err(|e| format!("{file}:{}: could not load: {e}", i + 1))?;
+        let winner = record.outcome.and_then(|outcome| outcome.winner());
+        book.add_game(record.mode, &record.history, winner)
+            .map_err(|e| format!("{file}:{}: {e}", i + 1))?;
+        games += 1;
+    }
+    Ok(games)
+}
+
+fn run(config: Config) -> Result<(), String> {
+    let mut book = Book {
+        moves: config.moves,
+        ..Book::default()
+    };
+    let mut games = 0;
+    for file in &config.games {
+        games += add_file(&mut book, file)?;
+    }
+    println!("{games} games, {} positions", book.len());
+
+    let data = ron::to_string(&book).map_err(|e| format!("could not save the book: {e}"))?;
+    std::fs::write(&config.out, data).map_err(|e| format!("could not write {}: {e}", config.out))
+}
+
+fn main() {
+    #[cfg(not(target_arch = "wasm32"))]
+    match parse_args(std::env::args().skip(1)) {
+        Ok(config) => {
+            if let Err(e) = run(config) {
+                eprintln!("error: {e}");
+                std::process::exit(1);
+            }
+        }
+        Err(e) => {
+            eprintln!("error: {e}");
+            std::process::exit(2);
+        }
+    }
+}
@@ -1,17 +1,57 @@
-use board::Board;
+use board::{editing::Brush, Board};
 use nannou::prelude::*;
 #[cfg(not(target_arch = "wasm32"))]
 use nannou_egui::Egui;
 
-mod board;
+pub mod ai;
+pub mod analysis;
+pub mod board;
+pub mod book;
+pub mod clock;
 #[cfg(not(target_arch = "wasm32"))]
 mod egui_defs;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod engine;
 #[cfg(target_arch = "wasm32")]
 mod js_comms;
-mod player;
+pub mod player;
+pub mod rules;
+pub mod search;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod stats;
+pub mod theme;
+pub mod view;
 
 const HEX_SIZE: f32 = 0.57;
 
+/// Where the file of the given name is kept among the settings of the game
+#[cfg(not(target_arch = "wasm32"))]
+fn config_path(file: &str) -> Option<std::path::PathBuf> {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .or_else(|| std::env::var_os("APPDATA"))
+        .map(std::path::PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| std::path::Path::new(&home).join(".config"))
+        })?;
+    Some(config.join("ccheckers").join(file))
+}
+
+/// Reads a settings file written by [`write_config`], if there is one
+#[cfg(not(target_arch = "wasm32"))]
+fn read_config<T: serde::de::DeserializeOwned>(file: &str) -> Option<T> {
+    let data = std::fs::read_to_string(config_path(file)?).ok()?;
+    ron::from_str(&data).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_config<T: serde::Serialize>(file: &str, value: &T) -> std::io::Result<()> {
+    let path = config_path(file).ok_or(std::io::ErrorKind::NotFound)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, ron::to_string(value).unwrap())
+}
+
 fn viewport_size(app: &App) -> f32 {
     let window_bounds = app.main_window().rect();
     f32::min(window_bounds.w(), window_bounds.h()) / 2.
@@ -20,10 +60,29 @@ fn viewport_size(app: &App) -> f32 {
 #[cfg_attr(target_arch = "wasm32", derive(Default))]
 pub struct Model {
     board: Board,
+    /// An earlier position of the game shown instead of the current one, with the number of moves
+    /// played to reach it
+    preview: Option<(usize, Board)>,
+    theme: theme::Theme,
+    view: view::View,
+    /// Clicking the board edits the position instead of moving while this is set
+    editing: Option<Brush>,
     #[cfg(not(target_arch = "wasm32"))]
     egui: Egui,
     #[cfg(not(target_arch = "wasm32"))]
     egui_data: egui_defs::EguiData,
+    /// Seats which are played by the computer rather than by clicking
+    #[cfg(not(target_arch = "wasm32"))]
+    bots: ai::Bots,
+}
+
+impl Model {
+    /// The board as it is drawn, which is the game unless an earlier position is being previewed
+    fn shown_board(&self) -> &Board {
+        self.preview
+            .as_ref()
+            .map_or(&self.board, |(_, board)| board)
+    }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -41,8 +100,13 @@ pub fn model(app: &App) -> Model {
 
     Model {
         board: Default::default(),
+        preview: None,
+        theme: theme::Theme::load(),
+        view: Default::default(),
+        editing: None,
         egui: Egui::from_window(&window),
-        egui_data: Default::default(),
+        egui_data: egui_defs::EguiData::new(),
+        bots: Default::default(),
     }
 }
 #[cfg(target_arch = "wasm32")]
@@ -67,23 +131,80 @@ pub async fn model(app: &App) -> Model {
 fn window_handler(app: &App, m: &Model, f: Frame) {
     let viewport_size = viewport_size(app);
 
-    f.clear(ANTIQUEWHITE);
-    let draw = app.draw().scale_axes(Vec3::splat(viewport_size));
-    m.board.draw(app, &draw);
+    f.clear(m.theme.background());
+    // drawings in window coordinates, such as the tooltip, share the state of the board's
+    let window = app.draw();
+    let draw = m.view.transform(&window, viewport_size);
+    m.shown_board()
+        .draw(&draw, &m.theme, &m.view, viewport_size);
+    if m.view.labels {
+        draw_tooltip(app, &window, m);
+    }
     draw.to_frame(app, &f).unwrap();
     #[cfg(not(target_arch = "wasm32"))]
     m.egui.draw_to_frame(&f).unwrap();
 }
 
-pub fn update(_app: &App, model: &mut Model, _update: Update) {
+/// Describes the cell under the cursor next to it
+fn draw_tooltip(app: &App, draw: &Draw, m: &Model) {
+    let board = m.shown_board();
+    let description = board
+        .position_of(&app.mouse, &m.view, viewport_size(app))
+        .and_then(|position| board.describe_cell(position));
+    if let Some(description) = description {
+        let lines = description.lines().count() as f32;
+        let longest = description.lines().map(str::len).max().unwrap_or(0) as f32;
+        let size = vec2(longest * 7.0 + 16.0, lines * 16.0 + 8.0);
+        let center = app.mouse.position() + vec2(size.x, -size.y) / 2.0 + vec2(12.0, -12.0);
+
+        draw.rect()
+            .xy(center)
+            .wh(size)
+            .color(srgba(0.1, 0.1, 0.1, 0.85));
+        draw.text(&description)
+            .xy(center)
+            .wh(size)
+            .font_size(12)
+            .color(WHITE);
+    }
+}
+
+pub fn update(_app: &App, model: &mut Model, update: Update) {
+    if model.editing.is_none() {
+        model.board.tick(update.since_last);
+    }
+    model.view.update(&model.board, update.since_last);
     #[cfg(not(target_arch = "wasm32"))]
-    egui_defs::define_ui(model, &_update);
+    {
+        egui_defs::define_ui(model, &update);
+        if model.board.outcome().is_none() && model.editing.is_none() {
+            if let Some(problem) = model.bots.play(&mut model.board) {
+                model.egui_data.seat_error = Some(problem);
+            }
+        }
+    }
     #[cfg(target_arch = "wasm32")]
     {
         if let Some(mode) = js_comms::recieve_reset() {
+            model.board.clocks.control = js_comms::time_control();
             model.board.reset(mode);
         }
+        if let Some(theme) = js_comms::recieve_theme() {
+            model.theme = theme;
+        }
+        if let Some(follow_turn) = js_comms::recieve_follow_turn() {
+            model.view.follow_turn = follow_turn;
+        }
+        if let Some(labels) = js_comms::recieve_labels() {
+            model.view.labels = labels;
+        }
+        if js_comms::recieve_hint() {
+            if let Some(path) = ai::hint(&model.board) {
+                model.board.set_path(path);
+            }
+        }
         js_comms::respond_load_request(model);
+        js_comms::publish_clocks(&model.board);
     }
 }
 
@@ -99,11 +220,34 @@ pub fn events(app: &App, m: &mut Model, e: Event) {
     } = e
     {
         match ev {
+            // clicking the board while looking at an earlier position goes back to the game
+            WindowEvent::MousePressed(MouseButton::Left) if m.preview.is_some() => m.preview = None,
             WindowEvent::MousePressed(MouseButton::Left) => {
-                if let Some(position) = m.board.position_of(&app.mouse, viewport_size(app)) {
-                    m.board.try_push_path(position);
+                let position = m.board.position_of(&app.mouse, &m.view, viewport_size(app));
+                match (position, m.editing) {
+                    (Some(position), Some(brush)) => m.board.paint(position, brush),
+                    (Some(position), None) => {
+                        m.board.try_push_path(position);
+                    }
+                    (None, _) => (),
+                }
+            }
+            WindowEvent::MousePressed(MouseButton::Right | MouseButton::Middle) => {
+                m.view.start_drag(app.mouse.position())
+            }
+            WindowEvent::MouseReleased(MouseButton::Right | MouseButton::Middle) => {
+                m.view.end_drag()
+            }
+            WindowEvent::MouseMoved(position) => m.view.drag_to(position, viewport_size(app)),
+            WindowEvent::MouseWheel(delta, _) => {
+                #[cfg(not(target_arch = "wasm32"))]
+                if m.egui.ctx().is_pointer_over_area() {
+                    return;
                 }
+                m.view
+                    .wheel(delta, app.mouse.position(), viewport_size(app))
             }
+            WindowEvent::Touch(touch) => m.view.touch(touch, viewport_size(app)),
             WindowEvent::KeyPressed(Key::Return) => m.board.commit_path(),
             WindowEvent::KeyPressed(Key::Left) => m.board.pop_path(),
             _ => (),
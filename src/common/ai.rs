@@ -0,0 +1,320 @@
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    collections::HashMap,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+use nannou::{
+    prelude::*,
+    rand::{rngs::StdRng, seq::SliceRandom, SeedableRng},
+};
+
+use super::{
+    board::Board,
+    book::Book,
+    player::{Controller, Seat, Turn},
+    search::SearchBot,
+};
+
+/// Anything that can pick a move for the player whose turn it currently is. Bots are sent to
+/// another thread to think, so that the window keeps drawing in the meantime.
+pub trait Bot: Send {
+    /// Returns the path to commit, or None if the bot has no move to offer
+    fn choose(&mut self, board: &Board) -> Option<Vec<IVec2>>;
+
+    /// What went wrong choosing the last move, e.g. an engine not answering in time, if anything
+    fn take_problem(&mut self) -> Option<String> {
+        None
+    }
+}
+
+/// True if [`bot_from_name`] knows the name, checked without starting anything it names
+pub fn is_bot_name(name: &str) -> bool {
+    match name.split_once(':') {
+        Some(("engine" | "network", target)) => {
+            cfg!(not(target_arch = "wasm32")) && !target.trim().is_empty()
+        }
+        Some(("search", depth)) => depth.parse::<u32>().is_ok(),
+        Some(_) => false,
+        None => matches!(name, "random" | "greedy" | "search"),
+    }
+}
+
+/// Constructs a bot from its name, as used on the command line: `random`, `greedy`, `search` or
+/// `search:<depth>`. On native, `engine:<command>` starts an external engine and
+/// `network:<address>` plays from another machine (see [`super::engine`]). Fails for unknown names
+/// and engines that do not start.
+pub fn bot_from_name(name: &str, seed: u64) -> Result<Box<dyn Bot>, String> {
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(command) = name.strip_prefix("engine:") {
+        return super::engine::ExternalBot::spawn(command, super::engine::DEFAULT_TIMEOUT)
+            .map(|bot| Box::new(bot) as Box<dyn Bot>);
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(address) = name.strip_prefix("network:") {
+        return Ok(Box::new(super::engine::NetworkBot::new(
+            address,
+            super::engine::DEFAULT_TIMEOUT,
+        )));
+    }
+
+    if let Some(depth) = name.strip_prefix("search:") {
+        if let Ok(depth) = depth.parse() {
+            return Ok(Box::new(SearchBot::new(depth)));
+        }
+    }
+
+    match name {
+        "random" => Ok(Box::new(RandomBot::new(seed))),
+        "greedy" => Ok(Box::new(GreedyBot::new(seed))),
+        "search" => Ok(Box::new(SearchBot::new(2))),
+        _ => Err(format!("there is no bot called {name}")),
+    }
+}
+
+/// The bot playing for the given seat, or None if a person does
+pub fn bot_for_seat(seat: &Seat, seed: u64) -> Result<Option<Box<dyn Bot>>, String> {
+    match &seat.controller {
+        Controller::Human => Ok(None),
+        Controller::Computer(name) => bot_from_name(name, seed).map(Some),
+        #[cfg(not(target_arch = "wasm32"))]
+        Controller::Engine(command) => {
+            super::engine::ExternalBot::spawn(command, super::engine::DEFAULT_TIMEOUT)
+                .map(|bot| Some(Box::new(bot) as Box<dyn Bot>))
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        Controller::Network(address) => Ok(Some(Box::new(super::engine::NetworkBot::new(
+            address,
+            super::engine::DEFAULT_TIMEOUT,
+        )))),
+        #[cfg(target_arch = "wasm32")]
+        Controller::Engine(_) => Err("external engines only run on native".to_string()),
+        #[cfg(target_arch = "wasm32")]
+        Controller::Network(_) => Err("network seats only run on native".to_string()),
+    }
+}
+
+/// A bot handed back by the thread it thought on, with the move it chose
+#[cfg(not(target_arch = "wasm32"))]
+type Answer = (Box<dyn Bot>, Option<Vec<IVec2>>);
+
+/// A bot thinking about its move on another thread
+#[cfg(not(target_arch = "wasm32"))]
+struct Thinking {
+    turn: Turn,
+    /// The position thought about, by its hash and the number of moves played to reach it
+    hash: u64,
+    moves: usize,
+    /// Where the bot arrives back with its move
+    answer: Receiver<Answer>,
+}
+
+/// The bots playing some of the seats of the game in the window. Bots think on another thread,
+/// so that the window keeps drawing and the clocks keep running while they do.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Default)]
+pub struct Bots {
+    seats: HashMap<Turn, Box<dyn Bot>>,
+    thinking: Option<Thinking>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Bots {
+    pub fn insert(&mut self, turn: Turn, bot: Box<dyn Bot>) {
+        self.seats.insert(turn, bot);
+    }
+
+    /// Stops every bot, throwing away the move of one still thinking
+    pub fn clear(&mut self) {
+        self.seats.clear();
+        self.thinking = None;
+    }
+
+    /// Sets the bot of the player to move thinking, if there is one
+    fn think(&mut self, board: &Board) {
+        if let Some(mut bot) = self.seats.remove(&board.turn) {
+            let position = board.clone();
+            let (sender, answer) = mpsc::channel();
+            thread::spawn(move || {
+                let path = bot.choose(&position);
+                let _ = sender.send((bot, path));
+            });
+            self.thinking = Some(Thinking {
+                turn: board.turn,
+                hash: board.hash(),
+                moves: board.history.len(),
+                answer,
+            });
+        }
+    }
+
+    /// Plays the move of the bot of the player to move once it has made up its mind, setting it
+    /// thinking if it has not started yet. Returns what went wrong with the bot, if anything.
+    pub fn play(&mut self, board: &mut Board) -> Option<String> {
+        let thinking = match &self.thinking {
+            Some(thinking) => thinking,
+            None => {
+                self.think(board);
+                return None;
+            }
+        };
+
+        let (mut bot, path) = match thinking.answer.try_recv() {
+            Ok(answer) => answer,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => {
+                let turn = thinking.turn;
+                self.thinking = None;
+                return Some(format!(
+                    "{}: the bot stopped working",
+                    board.player_name(turn)
+                ));
+            }
+        };
+        let thinking = self.thinking.take().unwrap();
+        // the move is for another position if the game went on or back in the meantime
+        if (board.turn, board.hash(), board.history.len())
+            == (thinking.turn, thinking.hash, thinking.moves)
+        {
+            match path {
+                Some(path) if board.play_path(&path) => (),
+                _ => board.skip_turn(),
+            }
+        }
+        let problem = bot.take_problem();
+        self.seats.insert(thinking.turn, bot);
+        problem.map(|problem| format!("{}: {problem}", board.player_name(thinking.turn)))
+    }
+}
+
+/// The move bringing the current player closest to their targets. Among equally good moves, the
+/// one covering the most ground is suggested.
+pub fn hint(board: &Board) -> Option<Vec<IVec2>> {
+    board.legal_paths().into_iter().min_by_key(|path| {
+        let mut after = board.clone();
+        after.play_path(path);
+        (after.distance_to_target(board.turn), -(path.len() as i32))
+    })
+}
+
+/// Plays from an opening book while the game is young and the book knows the position, and leaves
+/// the rest to another bot
+pub struct BookBot {
+    book: Arc<Book>,
+    rng: StdRng,
+    bot: Box<dyn Bot>,
+}
+
+impl BookBot {
+    pub fn new(book: Arc<Book>, seed: u64, bot: Box<dyn Bot>) -> Self {
+        Self {
+            book,
+            rng: StdRng::seed_from_u64(seed),
+            bot,
+        }
+    }
+}
+
+impl Bot for BookBot {
+    fn choose(&mut self, board: &Board) -> Option<Vec<IVec2>> {
+        self.book
+            .choose(board, &mut self.rng)
+            .or_else(|| self.bot.choose(board))
+    }
+
+    fn take_problem(&mut self) -> Option<String> {
+        self.bot.take_problem()
+    }
+}
+
+/// Plays any legal move
+pub struct RandomBot {
+    rng: StdRng,
+}
+
+impl RandomBot {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Bot for RandomBot {
+    fn choose(&mut self, board: &Board) -> Option<Vec<IVec2>> {
+        board.legal_paths().choose(&mut self.rng).cloned()
+    }
+}
+
+/// Plays the move which brings its pieces closest to their targets, breaking ties randomly
+pub struct GreedyBot {
+    rng: StdRng,
+}
+
+impl GreedyBot {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Bot for GreedyBot {
+    fn choose(&mut self, board: &Board) -> Option<Vec<IVec2>> {
+        let mut paths = board.legal_paths();
+        paths.shuffle(&mut self.rng);
+        paths.into_iter().min_by_key(|path| {
+            let (first, last) = (path.first().unwrap(), path.last().unwrap());
+            let piece = board.get(first).unwrap();
+            let tip = Board::target_tip(piece).unwrap();
+            Board::hex_distance(*last, tip) - Board::hex_distance(*first, tip)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::player::Mode;
+    use super::*;
+
+    #[test]
+    fn greedy_bot_moves_furthest_towards_the_targets() {
+        let mut board = Board::default();
+        board.reset(Mode::Two);
+        let mut bot = GreedyBot::new(0);
+        for _ in 0..20 {
+            let gain = |path: &Vec<IVec2>| {
+                let tip = Board::target_tip(board.get(&path[0]).unwrap()).unwrap();
+                Board::hex_distance(*path.last().unwrap(), tip) - Board::hex_distance(path[0], tip)
+            };
+            let best = board.legal_paths().iter().map(gain).min().unwrap();
+            let path = bot.choose(&board).unwrap();
+            assert_eq!(gain(&path), best);
+            assert!(board.play_path(&path));
+        }
+    }
+
+    #[test]
+    fn hints_are_legal_moves_closest_to_the_targets() {
+        for mode in [Mode::Two, Mode::Three, Mode::Six] {
+            let mut board = Board::default();
+            board.reset(mode);
+            let mut bot = RandomBot::new(0);
+            for _ in 0..6 {
+                let distance = |path: &Vec<IVec2>| {
+                    let mut after = board.clone();
+                    assert!(after.play_path(path));
+                    after.distance_to_target(board.turn)
+                };
+                let best = board.legal_paths().iter().map(distance).min();
+                let hinted = hint(&board).unwrap();
+                assert_eq!(Some(distance(&hinted)), best, "{mode:?}");
+
+                let path = bot.choose(&board).unwrap();
+                assert!(board.play_path(&path));
+            }
+        }
+    }
+}
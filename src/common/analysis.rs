@@ -0,0 +1,239 @@
+//! Looks back over a game to find where it was won and lost: how many moves every player still
+//! needed after each move, and which moves the engine finds much worse than its own choice
+
+use strum::IntoEnumIterator;
+
+use super::{
+    board::{endgame::RaceBudget, Board},
+    player::{Piece, Turn},
+    search::{SearchBot, WIN},
+};
+
+/// Most moves the estimate plays out before guessing the rest
+const MAX_PLAYOUT: usize = 200;
+/// Sum of the distances from the cells of a full target to its tip
+const FILLED_DISTANCE: i32 = 20;
+/// Moves the engine looks ahead to judge every move
+const ENGINE_DEPTH: u32 = 2;
+/// How far a move has to fall short of the engine's choice to count as a blunder, in cells of
+/// distance to the targets the player is ahead of the others
+pub const BLUNDER_CELLS: i32 = 6;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Blunder {
+    /// Index of the move in the game's history
+    pub index: usize,
+    pub turn: Turn,
+    /// How far the move falls short of the best one the engine finds, in cells of distance to the
+    /// targets the player is ahead of the others, or at least [`WIN`] if it misses a win
+    pub loss: i32,
+}
+
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Analysis {
+    /// The players of the game, in turn order
+    pub turns: Vec<Turn>,
+    /// For the position before the first move and after every move, the estimated moves every
+    /// player needs to finish, in the order of `turns`
+    pub estimates: Vec<Vec<f32>>,
+    pub blunders: Vec<Blunder>,
+}
+
+/// Roughly how many moves the player needs to bring all of their pieces into their targets if
+/// nobody else moves. Races are solved exactly when that is quick; otherwise the player moves
+/// whichever piece gets closest to its target until nothing gets closer, and every cell still to
+/// go after that counts as a move.
+pub fn moves_to_finish(board: &Board, turn: Turn) -> f32 {
+    if board.is_race() {
+        if let Some(line) = board.shortest_finish(turn, RaceBudget::ANALYSIS) {
+            return line.len() as f32;
+        }
+    }
+
+    let mut board = board.clone();
+    board.turn = turn;
+    let mut moves = 0;
+    while moves < MAX_PLAYOUT && !board.finished(turn) {
+        let best = board
+            .legal_paths()
+            .into_iter()
+            .map(|path| {
+                let tip = Board::target_tip(board.get(&path[0]).unwrap()).unwrap();
+                let end = *path.last().unwrap();
+                (
+                    Board::hex_distance(end, tip) - Board::hex_distance(path[0], tip),
+                    path,
+                )
+            })
+            .min_by_key(|(gain, _)| *gain);
+        match best {
+            Some((gain, path)) if gain < 0 => {
+                board.move_piece(&path[0], path.last().unwrap());
+                moves += 1;
+            }
+            _ => break,
+        }
+    }
+    if board.finished(turn) {
+        return moves as f32;
+    }
+
+    let kinds = Piece::iter()
+        .filter(|&kind| turn.owns(kind, board.mode))
+        .count() as i32;
+    let left = (board.distance_to_target(turn) - FILLED_DISTANCE * kinds).max(1);
+    (moves as i32 + left) as f32
+}
+
+impl Analysis {
+    /// Estimates every position of the game's history and looks for blunders
+    pub fn of(board: &Board) -> Self {
+        let turns = board.mode.turns().collect::<Vec<_>>();
+        let positions = board.replay();
+        let estimates = positions
+            .iter()
+            .map(|position| {
+                turns
+                    .iter()
+                    .map(|&turn| moves_to_finish(position, turn))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        // every move is compared to the best one the engine finds in the position before it
+        let mut engine = SearchBot::new(ENGINE_DEPTH);
+        let blunders = board
+            .history
+            .iter()
+            .zip(positions.windows(2))
+            .enumerate()
+            .filter_map(|(index, (played, positions))| {
+                let best = engine.value(&positions[0], played.turn, ENGINE_DEPTH);
+                let value = engine.value(&positions[1], played.turn, ENGINE_DEPTH - 1);
+                let loss = best - value;
+                (loss >= BLUNDER_CELLS).then_some(Blunder {
+                    index,
+                    turn: played.turn,
+                    loss,
+                })
+            })
+            .collect();
+
+        Self {
+            turns,
+            estimates,
+            blunders,
+        }
+    }
+
+    /// The estimates as a table of text, one position per line, followed by the blunders
+    pub fn text(&self, board: &Board) -> String {
+        let mut text = format!(
+            "move\t{}\n",
+            self.turns
+                .iter()
+                .map(|&turn| board.player_name(turn))
+                .collect::<Vec<_>>()
+                .join("\t")
+        );
+        for (i, estimates) in self.estimates.iter().enumerate() {
+            let estimates = estimates
+                .iter()
+                .map(|estimate| estimate.to_string())
+                .collect::<Vec<_>>();
+            text += &format!("{i}\t{}\n", estimates.join("\t"));
+        }
+        for blunder in &self.blunders {
+            text += &format!("{}\n", self.describe(blunder, board));
+        }
+        text
+    }
+
+    pub fn describe(&self, blunder: &Blunder, board: &Board) -> String {
+        let loss = if blunder.loss >= WIN {
+            "missed a win".to_string()
+        } else {
+            format!("fell {} cells behind the best move", blunder.loss)
+        };
+        format!(
+            "Move {} by {} ({}) {loss}",
+            blunder.index + 1,
+            board.player_name(blunder.turn),
+            Board::path_name(&board.history[blunder.index].path),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nannou::prelude::*;
+
+    use super::super::{board::editing::Brush, player::Mode};
+    use super::*;
+
+    /// A race for Player1 with one piece left to bring home and a ladder of Player3's stones to
+    /// jump down, which only works while Player1's own stone at (-1, 0) stays where it is
+    fn ladder() -> Board {
+        let mut board = Board::default();
+        board.reset(Mode::Two);
+        board.clear();
+        for piece in [Piece::Player2, Piece::Player6, Piece::Player1] {
+            for cell in Board::target(piece) {
+                board.paint(cell, Brush::Paint(piece));
+            }
+        }
+        for piece in [Piece::Player3, Piece::Player4, Piece::Player5] {
+            for cell in Board::home(piece) {
+                board.paint(cell, Brush::Paint(piece));
+            }
+        }
+        for cell in [ivec2(-1, -4), ivec2(-2, -4)] {
+            board.paint(cell, Brush::Paint(Piece::None));
+        }
+        for cell in [ivec2(-1, 5), ivec2(-1, 0)] {
+            board.paint(cell, Brush::Paint(Piece::Player1));
+        }
+        for y in [4, 2, -2] {
+            board.paint(ivec2(-1, y), Brush::Paint(Piece::Player3));
+        }
+        board.start_from_here();
+        board
+    }
+
+    #[test]
+    fn jumping_down_the_ladder_is_no_blunder() {
+        let mut board = ladder();
+        board.play_path(&[
+            ivec2(-1, 5),
+            ivec2(-1, 3),
+            ivec2(-1, 1),
+            ivec2(-1, -1),
+            ivec2(-1, -3),
+        ]);
+        assert_eq!(Analysis::of(&board).blunders, Vec::new());
+    }
+
+    #[test]
+    fn stepping_off_the_ladder_is_a_blunder() {
+        let mut board = ladder();
+        board.play_path(&[ivec2(-1, 0), ivec2(0, -1)]);
+        let analysis = Analysis::of(&board);
+        assert_eq!(
+            analysis.blunders,
+            vec![Blunder {
+                index: 0,
+                turn: Turn::Player1,
+                loss: 10,
+            }]
+        );
+        assert_eq!(analysis.estimates.len(), 2);
+        assert_eq!(
+            analysis.describe(&analysis.blunders[0], &board),
+            format!(
+                "Move 1 by {} ({}) fell 10 cells behind the best move",
+                board.player_name(Turn::Player1),
+                Board::path_name(&board.history[0].path)
+            )
+        );
+    }
+}
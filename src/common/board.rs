@@ -1,19 +1,31 @@
-use std::collections::HashMap;
-
+use cells::Cells;
 use itertools::Itertools;
-use nannou::{
-    color::{Alpha, Shade},
-    prelude::*,
-    state::Mouse,
-};
+use nannou::{prelude::*, rand::seq::SliceRandom, state::Mouse};
+use positions::Positions;
+use scene::{Paint, Shape};
 use serde::{Serialize, Deserialize};
 use strum::IntoEnumIterator;
 
 use super::{
-    player::{Mode, Piece, Turn},
+    clock::{Clocks, FlagFall},
+    player::{Mode, Piece, Seat, Turn},
+    rules::{LimitResult, Rules},
+    theme::Theme,
+    view::View,
     HEX_SIZE,
 };
 
+pub mod cells;
+pub mod editing;
+pub mod endgame;
+mod positions;
+pub mod puzzle;
+mod raster;
+pub mod scene;
+pub mod scoring;
+mod svg;
+pub mod zobrist;
+
 /// "Divides" v1 by v2
 fn divide(v1: IVec2, v2: IVec2) -> Option<i32> {
     v1.x.checked_div(v2.x)
@@ -30,39 +42,116 @@ fn divide(v1: IVec2, v2: IVec2) -> Option<i32> {
 /// there, the point (0, 4) is in the top right corner within the central hexagon, while (0, -4) is
 /// in the bottom left corner of the hexagon. Similarly, the point at (4, 0) is at the right, while
 /// (-4, 0) is in the left. By this, the top left is at (-4, 4) and the bottom right is at (4, -4)
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Board {
-    backing: HashMap<IVec2, Piece>,
+    backing: Cells,
     path: Vec<IVec2>,
     pub turn: Turn,
     pub mode: Mode,
+    #[serde(default)]
+    pub rules: Rules,
+    /// Every move committed since the last reset, in order
+    #[serde(default)]
+    pub history: Vec<Move>,
+    /// Players who broke the rules and take no further part in the game
+    #[serde(default)]
+    pub forfeited: Vec<Turn>,
+    #[serde(default)]
+    pub clocks: Clocks,
+    /// Hash of every position reached since the last reset, for spotting repetitions
+    #[serde(default)]
+    positions: Positions,
+    /// Who plays each turn, in turn order. Seats left out are shown under the turn's own name.
+    #[serde(default)]
+    pub seats: Vec<Seat>,
+    /// Players who moved all of their pieces into their targets, in the order they did
+    #[serde(default)]
+    pub placings: Vec<Turn>,
+    /// What the last moves of the history changed besides the pieces, for taking them back. Games
+    /// saved before this was kept have none for their earlier moves.
+    #[serde(default)]
+    undo: Vec<Undo>,
+}
+
+/// A committed move
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Move {
+    pub turn: Turn,
+    pub path: Vec<IVec2>,
+}
+
+/// The state of the game before a committed move, other than where the pieces were
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+struct Undo {
+    positions: usize,
+    forfeited: usize,
+    placings: usize,
+    clocks: Clocks,
+}
+
+/// A move made with [`Board::make_move`], to be taken back with [`Board::unmake_move`]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Made {
+    /// Where the piece moved from and to, or None for a pass
+    moved: Option<(IVec2, IVec2)>,
+    turn: Turn,
+    placings: usize,
+}
+
+/// How a game ended
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Outcome {
+    /// The player reached their targets, or was the last one not to forfeit
+    Won(Turn),
+    /// The game was cut short by a repetition or the move limit, with the player closest to
+    /// their targets
+    WonOnProgress(Turn),
+    Draw,
+}
+
+impl Outcome {
+    pub fn winner(&self) -> Option<Turn> {
+        match *self {
+            Outcome::Won(turn) | Outcome::WonOnProgress(turn) => Some(turn),
+            Outcome::Draw => None,
+        }
+    }
 }
 
 impl Default for Board {
     fn default() -> Self {
         Self {
-            backing: {
-                let center = (-4..5)
-                    .cartesian_product(-4..5)
-                    .map(|(a, b)| IVec2::new(a, b));
-
-                center
-                    .chain(Self::region_2())
-                    .chain(Self::region_3())
-                    .chain(Self::region_5())
-                    .chain(Self::region_6())
-                    .map(|v| (v, Piece::None))
-                    .collect()
-            },
+            backing: Cells::empty(),
             path: Default::default(),
             turn: Turn::default(),
             mode: Mode::default(),
+            rules: Rules::default(),
+            history: Vec::new(),
+            forfeited: Vec::new(),
+            clocks: Clocks::default(),
+            positions: Positions::default(),
+            seats: Vec::new(),
+            placings: Vec::new(),
+            undo: Vec::new(),
         }
     }
 }
 
 /// Iterators for each home region of the board
 impl Board {
+    /// Every position on the board
+    fn all_positions() -> impl Iterator<Item = IVec2> {
+        let center = (-4..5)
+            .cartesian_product(-4..5)
+            .map(|(a, b)| IVec2::new(a, b));
+
+        center
+            .chain(Self::region_2())
+            .chain(Self::region_3())
+            .chain(Self::region_5())
+            .chain(Self::region_6())
+    }
+
     fn region_1() -> impl Iterator<Item = IVec2> {
         (0..5).flat_map(|x| (5 - x..5).map(move |y| ivec2(x, y)))
     }
@@ -82,6 +171,31 @@ impl Board {
     fn region_6() -> impl Iterator<Item = IVec2> {
         (5..9).flat_map(|x| (-4..(5 - x)).map(move |y| ivec2(x, y)))
     }
+
+    /// The region a piece starts in
+    pub fn home(piece: Piece) -> Vec<IVec2> {
+        match piece {
+            Piece::None => Vec::new(),
+            Piece::Player1 => Self::region_1().collect(),
+            Piece::Player2 => Self::region_2().collect(),
+            Piece::Player3 => Self::region_3().collect(),
+            Piece::Player4 => Self::region_4().collect(),
+            Piece::Player5 => Self::region_5().collect(),
+            Piece::Player6 => Self::region_6().collect(),
+        }
+    }
+
+    /// The region a piece has to reach, which is always the one opposite to its home
+    pub fn target(piece: Piece) -> Vec<IVec2> {
+        Self::home(piece.opposite())
+    }
+
+    /// The corner of the target region furthest from the center of the board
+    pub fn target_tip(piece: Piece) -> Option<IVec2> {
+        Self::target(piece)
+            .into_iter()
+            .max_by_key(|v| Self::hex_distance(IVec2::ZERO, *v))
+    }
 }
 
 impl Board {
@@ -105,6 +219,12 @@ impl Board {
         ]
     }
 
+    /// Number of single steps needed to walk from one position to the other on an empty board
+    pub fn hex_distance(v1: IVec2, v2: IVec2) -> i32 {
+        let dv = v2 - v1;
+        dv.x.abs().max(dv.y.abs()).max((dv.x + dv.y).abs())
+    }
+
     pub fn cardinal_distance(v1: IVec2, v2: IVec2) -> Option<(IVec2, i32)> {
         let dv = v2 - v1;
         Self::cardinals()
@@ -114,25 +234,95 @@ impl Board {
 
     fn fill_area(&mut self, positions: impl Iterator<Item = IVec2>, piece: Piece) {
         for p in positions {
-            *self.backing.get_mut(&p).unwrap() = piece;
+            self.backing.set(p, piece);
         }
     }
 
     pub fn reset(&mut self, mode: Mode) {
         self.mode = mode;
-        self.backing.values_mut().for_each(|p| *p = Piece::None);
-        self.path.clear();
+        self.backing = Cells::empty();
         self.fill_area(Self::region_1(), Piece::Player1);
         self.fill_area(Self::region_2(), Piece::Player2);
         self.fill_area(Self::region_3(), Piece::Player3);
         self.fill_area(Self::region_4(), Piece::Player4);
         self.fill_area(Self::region_5(), Piece::Player5);
         self.fill_area(Self::region_6(), Piece::Player6);
-        self.turn = Turn::Player1
+        self.turn = Turn::Player1;
+        self.start_from_here();
+    }
+
+    /// Starts a new game from the position on the board as it is, e.g. after editing it
+    pub fn start_from_here(&mut self) {
+        self.path.clear();
+        self.history.clear();
+        self.forfeited.clear();
+        self.placings.clear();
+        self.undo.clear();
+        self.clocks.start(self.mode);
+        self.positions.restart(self.hash());
+    }
+
+    /// Zobrist hash of the position, i.e. where the pieces are and whose turn it is. The piece
+    /// placement part is kept up to date by every move, so this is cheap.
+    pub fn hash(&self) -> u64 {
+        self.backing.hash() ^ zobrist::turn_key(self.turn)
     }
 
     pub fn move_piece(&mut self, from: &IVec2, to: &IVec2) {
-        *self.backing.get_mut(to).unwrap() = std::mem::take(self.backing.get_mut(from).unwrap())
+        let piece = self.backing.get(*from).unwrap();
+        self.backing.set(*from, Piece::None);
+        self.backing.set(*to, piece);
+    }
+
+    /// The player the given kind of piece belongs to in this mode
+    pub fn owner(&self, piece: Piece) -> Turn {
+        self.mode
+            .turns()
+            .find(|turn| turn.owns(piece, self.mode))
+            .unwrap_or_default()
+    }
+
+    pub fn seat(&self, turn: Turn) -> Option<&Seat> {
+        let index = self.mode.turns().position(|other| other == turn)?;
+        self.seats.get(index)
+    }
+
+    /// How the player of the given turn is called in messages: their avatar and name if they
+    /// have one, otherwise the turn's own name
+    pub fn player_name(&self, turn: Turn) -> String {
+        match self.seat(turn) {
+            Some(seat) if !seat.name.is_empty() => {
+                format!("{} {}", seat.avatar, seat.name).trim().to_string()
+            }
+            _ => turn.to_string(),
+        }
+    }
+
+    /// Names a position the way players write it down: the column as a letter from `a` to `q`,
+    /// followed by the row as a number from 1 to 17. The center of the board is `i9`.
+    pub fn cell_name(position: IVec2) -> String {
+        format!(
+            "{}{}",
+            (b'a' + (position.x + 8) as u8) as char,
+            position.y + 9
+        )
+    }
+
+    /// Names a move by the cells it passes through, e.g. `c5-e7-g9`
+    pub fn path_name(path: &[IVec2]) -> String {
+        path.iter().map(|&p| Self::cell_name(p)).join("-")
+    }
+
+    /// The inverse of [`Board::cell_name`]. Does not check whether the position is on the board.
+    pub fn parse_cell(name: &str) -> Option<IVec2> {
+        let mut chars = name.chars();
+        let column = chars.next().filter(|c| ('a'..='q').contains(c))?;
+        let row = chars
+            .as_str()
+            .parse::<i32>()
+            .ok()
+            .filter(|r| (1..=17).contains(r))?;
+        Some(ivec2(column as i32 - 'a' as i32 - 8, row - 9))
     }
 
     /// Converts a board position into a viewport position
@@ -142,31 +332,226 @@ impl Board {
     }
 
     /// Converts the screen position (say, of the cursor) into a position on the board, if the
-    /// position is within the board's bounds.
-    pub fn position_of(&self, mouse: &Mouse, scale: f32) -> Option<IVec2> {
+    /// position is within the board's bounds. Undoes however the view turns, zooms and pans the
+    /// board.
+    pub fn position_of(&self, mouse: &Mouse, view: &View, scale: f32) -> Option<IVec2> {
         let (bx, by) = Self::bases();
         let inverter = mat2(bx, by).inverse();
-        let predicted_f32 = inverter * (mouse.position() / scale) + Point2::ONE / 2.;
+        let predicted_f32 = inverter * view.to_board(mouse.position(), scale) + Point2::ONE / 2.;
         let predicted = predicted_f32.floor().as_i32();
 
-        self.backing.contains_key(&predicted).then_some(predicted)
+        self.backing.contains(predicted).then_some(predicted)
     }
 
     pub fn get(&self, position: &IVec2) -> Option<Piece> {
-        self.backing.get(position).copied()
+        self.backing.get(*position)
+    }
+
+    /// All occupied positions along with the piece occupying them
+    pub fn pieces(&self) -> impl Iterator<Item = (IVec2, Piece)> + '_ {
+        self.backing.iter().filter(|(_, piece)| !piece.is_none())
+    }
+
+    /// True if every piece the given player owns sits in its target region. Under the
+    /// anti-spoiling rule, a target is also considered reached when it is full, an opponent's
+    /// piece sits in it and at least one of the player's pieces fills the rest. A target full of
+    /// nothing but opponents, as at the start, is not reached.
+    pub fn finished(&self, turn: Turn) -> bool {
+        let own = Piece::iter()
+            .filter(|&kind| turn.owns(kind, self.mode))
+            .fold(0, |mask, kind| mask | self.backing.bitboard(kind));
+        let empty = self.backing.bitboard(Piece::None);
+        turn != Turn::None
+            && Piece::iter()
+                .filter(|&kind| turn.owns(kind, self.mode))
+                .all(|kind| {
+                    let target = Cells::home_mask(kind.opposite());
+                    let all_arrived = self.backing.bitboard(kind) & !target == 0;
+                    let filled_around_spoilers =
+                        target & empty == 0 && target & own != 0 && target & !own & !empty != 0;
+                    all_arrived || self.rules.anti_spoiling && filled_around_spoilers
+                })
+    }
+
+    /// Players still taking turns: those who have neither forfeited nor finished
+    pub fn active_players(&self) -> impl Iterator<Item = Turn> + '_ {
+        self.mode
+            .turns()
+            .filter(|turn| !self.forfeited.contains(turn) && !self.placings.contains(turn))
+    }
+
+    /// The first player (in turn order) to have moved all of their pieces into their targets, or
+    /// the last player standing if everyone else forfeited. When playing on, there is no winner
+    /// until at most one player is still taking turns.
+    pub fn winner(&self) -> Option<Turn> {
+        if self.turn == Turn::None {
+            return None;
+        }
+
+        if self.rules.play_on {
+            let mut active = self.active_players();
+            return match (active.next(), active.next()) {
+                (_, Some(_)) => None,
+                (last, None) => self.placings.first().copied().or(last),
+            };
+        }
+
+        let mut remaining = self
+            .mode
+            .turns()
+            .filter(|turn| !self.forfeited.contains(turn));
+        let last_standing = match (remaining.next(), remaining.next()) {
+            (Some(turn), None) => Some(turn),
+            _ => None,
+        };
+        self.mode
+            .turns()
+            .filter(|turn| !self.forfeited.contains(turn))
+            .find(|&turn| self.finished(turn))
+            .or(last_standing)
+    }
+
+    /// Sum of the distances from each of the player's pieces to the tip of the piece's target
+    /// region. This reaches its minimum exactly when every target region is filled.
+    pub fn distance_to_target(&self, turn: Turn) -> i32 {
+        self.pieces()
+            .filter(|(_, piece)| turn.owns(*piece, self.mode))
+            .map(|(pos, piece)| Self::hex_distance(pos, Self::target_tip(piece).unwrap()))
+            .sum()
+    }
+
+    /// How the game ended, or None if it is still going
+    pub fn outcome(&self) -> Option<Outcome> {
+        if let Some(winner) = self.winner() {
+            return Some(Outcome::Won(winner));
+        }
+        if self.turn == Turn::None {
+            return None;
+        }
+
+        let repeated = self
+            .rules
+            .repetition
+            .is_some_and(|n| self.positions.count(self.hash()) >= n);
+        let out_of_moves = self
+            .rules
+            .move_limit
+            .is_some_and(|limit| self.history.len() >= limit);
+        if !repeated && !out_of_moves {
+            return None;
+        }
+
+        Some(match self.rules.limit_result {
+            LimitResult::Draw => Outcome::Draw,
+            LimitResult::Progress => {
+                let mut standings = self
+                    .mode
+                    .turns()
+                    .filter(|turn| !self.forfeited.contains(turn))
+                    .map(|turn| (self.distance_to_target(turn), turn))
+                    .collect::<Vec<_>>();
+                standings.sort_by_key(|(distance, _)| *distance);
+                match standings[..] {
+                    [(first, _), (second, _), ..] if first == second => Outcome::Draw,
+                    [(_, turn), ..] => Outcome::WonOnProgress(turn),
+                    [] => Outcome::Draw,
+                }
+            }
+        })
+    }
+
+    /// Number of the player's pieces still sitting in one of their home regions
+    pub fn pieces_at_home(&self, turn: Turn) -> usize {
+        let (homes, own) = Piece::iter()
+            .filter(|&kind| turn.owns(kind, self.mode))
+            .fold((0, 0), |(homes, own), kind| {
+                (
+                    homes | Cells::home_mask(kind),
+                    own | self.backing.bitboard(kind),
+                )
+            });
+        (homes & own).count_ones() as usize
+    }
+
+    /// Forfeits the player if they broke the home limit with the move they just made
+    fn enforce_home_limit(&mut self, turn: Turn) {
+        if let Some(limit) = self.rules.home_limit {
+            let moves_made = self.history.iter().filter(|m| m.turn == turn).count();
+            if moves_made >= limit.after_moves && self.pieces_at_home(turn) > limit.max_pieces {
+                self.forfeited.push(turn);
+            }
+        }
+    }
+
+    /// Passes the turn on to the next player who has neither forfeited nor finished
+    fn advance_turn(&mut self) {
+        for _ in 0..self.mode.players() {
+            self.turn = self.mode.next_turn(self.turn);
+            if !self.forfeited.contains(&self.turn) && !self.placings.contains(&self.turn) {
+                break;
+            }
+        }
+    }
+
+    /// Places everyone who finished with the last move, the player who made it first
+    fn record_placings(&mut self, mover: Turn) {
+        let order = std::iter::once(mover).chain(self.mode.turns().filter(|&turn| turn != mover));
+        for turn in order.collect::<Vec<_>>() {
+            if !self.placings.contains(&turn)
+                && !self.forfeited.contains(&turn)
+                && self.finished(turn)
+            {
+                self.placings.push(turn);
+            }
+        }
+    }
+
+    /// Every path the current player could commit, one per reachable destination. Paths are either
+    /// a single step or a chain of jumps.
+    pub fn legal_paths(&self) -> Vec<Vec<IVec2>> {
+        let empty = self.backing.bitboard(Piece::None);
+        let is_free = |i: usize| empty & 1 << i != 0;
+        let own = Piece::iter()
+            .filter(|&kind| self.turn.owns(kind, self.mode))
+            .fold(0, |mask, kind| mask | self.backing.bitboard(kind));
+
+        let mut paths = Vec::new();
+        for start in cells::indices(own) {
+            for neighbour in Cells::neighbours(start).into_iter().flatten() {
+                if is_free(neighbour) {
+                    paths.push(vec![Cells::position(start), Cells::position(neighbour)]);
+                }
+            }
+
+            let mut frontier = vec![vec![start]];
+            let mut visited = 1u128 << start;
+            while let Some(path) = frontier.pop() {
+                let at = *path.last().unwrap();
+                for (over, landing) in Cells::jumps(at).into_iter().flatten() {
+                    if !is_free(over) && is_free(landing) && visited & 1 << landing == 0 {
+                        visited |= 1 << landing;
+                        let mut next = path.clone();
+                        next.push(landing);
+                        paths.push(next.iter().map(|&i| Cells::position(i)).collect());
+                        frontier.push(next);
+                    }
+                }
+            }
+        }
+        paths
     }
 
     /// Checks if jumping from the first to the second position is legal, taking into account the
-    /// rest of the path. Both positions given must be valid positions on the board.
+    /// rest of the path. Positions off the board are never legal.
     pub fn is_legal(&self, new: IVec2) -> bool {
         if let Some(&starts) = self.path.last() {
-            self.backing.get(&new).unwrap().is_none()
+            self.backing.get(new).is_some_and(|piece| piece.is_none())
                 && match Self::cardinal_distance(starts, new) {
-                    Some((_, x)) if x == 1 => self.path.len() == 1,
-                    Some((cardinal, x)) if x == 2 => {
+                    Some((_, 1)) => self.path.len() == 1,
+                    Some((cardinal, 2)) => {
                         if self.path.len() > 1
                             && Self::cardinal_distance(
-                                *self.path.get(0).unwrap(),
+                                *self.path.first().unwrap(),
                                 *self.path.get(1).unwrap(),
                             )
                             .unwrap()
@@ -174,7 +559,9 @@ impl Board {
                         {
                             return false;
                         }
-                        !self.backing.get(&(starts + cardinal)).unwrap().is_none()
+                        self.backing
+                            .get(starts + cardinal)
+                            .is_some_and(|piece| !piece.is_none())
                     }
                     _ => false,
                 }
@@ -186,7 +573,7 @@ impl Board {
     }
 
     pub fn try_push_path(&mut self, new: IVec2) -> bool {
-        if self.is_legal(new) {
+        if self.outcome().is_none() && self.is_legal(new) {
             self.path.push(new);
             true
         } else {
@@ -194,84 +581,1059 @@ impl Board {
         }
     }
 
+    /// Replaces the current path without checking it, e.g. to show a suggested move
+    pub fn set_path(&mut self, path: Vec<IVec2>) {
+        self.path = path;
+    }
+
     pub fn pop_path(&mut self) {
         self.path.pop();
     }
 
+    /// Counts the sequences of `depth` moves that can be played from this position, checking every
+    /// generated path against `is_legal` along the way
+    pub fn perft(&self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        self.legal_paths()
+            .into_iter()
+            .map(|path| {
+                let mut next = self.clone();
+                assert!(next.play_path(&path), "generated illegal path {path:?}");
+                next.perft(depth - 1)
+            })
+            .sum()
+    }
+
+    /// Replaces the current path with the given one and commits it. If any part of the path is
+    /// illegal, the board is left as it was (minus the current path) and false is returned.
+    pub fn play_path(&mut self, path: &[IVec2]) -> bool {
+        self.path.clear();
+        let legal = path.len() > 1 && path.iter().all(|&p| self.try_push_path(p));
+        if legal {
+            self.commit_path();
+        } else {
+            self.path.clear();
+        }
+        legal
+    }
+
+    /// Takes back the last committed move, giving the turn back to the player who made it
+    pub fn undo_move(&mut self) -> Option<Move> {
+        let last = self.history.pop()?;
+        self.move_piece(last.path.last().unwrap(), &last.path[0]);
+        self.turn = last.turn;
+        self.path.clear();
+        match self.undo.pop() {
+            Some(undo) => {
+                // turns passed and players forfeited after the move are taken back with it
+                self.positions.truncate(undo.positions);
+                self.forfeited.truncate(undo.forfeited);
+                self.placings.truncate(undo.placings);
+                self.clocks = undo.clocks;
+            }
+            None => {
+                self.positions.pop();
+                let placings = std::mem::take(&mut self.placings);
+                self.placings = placings
+                    .into_iter()
+                    .filter(|&turn| self.finished(turn))
+                    .collect();
+            }
+        }
+        Some(last)
+    }
+
+    /// Plays a path `legal_paths` returned, or passes for an empty one, as quickly as possible
+    /// for looking ahead: the move is not checked, the clocks do not run and the home limit is not
+    /// enforced. The history and positions are kept, so that the move limit and repetitions still
+    /// end the game.
+    pub fn make_move(&mut self, path: &[IVec2]) -> Made {
+        let made = Made {
+            moved: path.first().zip(path.last()).map(|(&from, &to)| (from, to)),
+            turn: self.turn,
+            placings: self.placings.len(),
+        };
+        if let Some((from, to)) = made.moved {
+            self.move_piece(&from, &to);
+            self.history.push(Move {
+                turn: self.turn,
+                path: path.to_vec(),
+            });
+            self.record_placings(self.turn);
+        }
+        self.advance_turn();
+        self.positions.push(self.hash());
+        made
+    }
+
+    /// Takes back a move made with `make_move`. Moves must be taken back in reverse order.
+    pub fn unmake_move(&mut self, made: Made) {
+        if let Some((from, to)) = made.moved {
+            self.move_piece(&to, &from);
+            self.history.pop();
+        }
+        self.turn = made.turn;
+        self.positions.pop();
+        self.placings.truncate(made.placings);
+    }
+
+    /// The board as it was after each of the moves in its history, starting with the position
+    /// before the first one. Each position shows the move leading to it as the current path.
+    pub fn replay(&self) -> Vec<Board> {
+        let mut board = self.clone();
+        board.path.clear();
+        let mut positions = vec![board.clone()];
+        while let Some(last) = board.undo_move() {
+            positions.last_mut().unwrap().path = last.path;
+            positions.push(board.clone());
+        }
+        positions.reverse();
+        positions
+    }
+
+    /// Gives up the current player's move, e.g. when they have nothing legal to play
+    pub fn skip_turn(&mut self) {
+        self.path.clear();
+        self.clocks.passed();
+        self.advance_turn();
+        self.positions.push(self.hash());
+    }
+
+    /// Runs the current player's clock, dealing with them as the time control says if their flag
+    /// falls
+    pub fn tick(&mut self, elapsed: std::time::Duration) {
+        if self.turn == Turn::None
+            || self.outcome().is_some()
+            || !self.clocks.run(self.turn, elapsed)
+        {
+            return;
+        }
+
+        let flag_fall = self.clocks.control.map(|c| c.flag_fall).unwrap_or_default();
+        match flag_fall {
+            FlagFall::Lose => {
+                self.forfeited.push(self.turn);
+                self.skip_turn();
+            }
+            FlagFall::Pass => self.skip_turn(),
+            FlagFall::RandomMove => {
+                let path = self
+                    .legal_paths()
+                    .choose(&mut nannou::rand::thread_rng())
+                    .cloned();
+                if !path.is_some_and(|path| self.play_path(&path)) {
+                    self.skip_turn();
+                }
+            }
+        }
+    }
+
     /// If the path is long enough to move, does the move and returns true. Otherwise does nothing
     /// and returns false.
     pub fn commit_path(&mut self) {
         if self.path.len() > 1 {
             let (first, last) = (*self.path.first().unwrap(), *self.path.last().unwrap());
+            self.undo.push(Undo {
+                positions: self.positions.len(),
+                forfeited: self.forfeited.len(),
+                placings: self.placings.len(),
+                clocks: self.clocks.clone(),
+            });
             self.move_piece(&first, &last);
-            self.path.clear();
-            self.turn = self.mode.next_turn(self.turn);
+            self.history.push(Move {
+                turn: self.turn,
+                path: std::mem::take(&mut self.path),
+            });
+            self.clocks.moved(self.turn);
+            self.enforce_home_limit(self.turn);
+            self.record_placings(self.turn);
+            self.advance_turn();
+            self.positions.push(self.hash());
         }
     }
 }
 
 impl Board {
     const HIGHLIGHT_WIDTH: f32 = Self::WIDTH + Self::BASE_SPACING / 5.0;
+    const TRIANGLE_STROKE: f32 = 0.005;
 
-    pub fn draw(&self, app: &App, draw: &Draw) {
-        self.draw_board_background(draw);
-        self.draw_pieces(draw);
-        self.draw_path(draw);
-    }
-
-    fn draw_board_background(&self, draw: &Draw) {
-        let hex_coords = (0..)
+    /// Corners of the central hexagon, counterclockwise starting from the rightmost one
+    fn hexagon() -> impl Iterator<Item = Point2> + Clone {
+        (0..)
             .map(|i| f32::PI() * i as f32 / 3.0)
-            .map(|rad| pt2(rad.cos(), rad.sin()) * HEX_SIZE);
-
-        let hex = hex_coords.clone().take(6);
-        draw.polygon().points(hex);
+            .map(|rad| pt2(rad.cos(), rad.sin()) * HEX_SIZE)
+    }
 
-        hex_coords
+    /// The corners of the triangle drawn behind each home region, with the piece starting there
+    fn home_triangles() -> impl Iterator<Item = ([Point2; 3], Piece)> {
+        Self::hexagon()
             .tuple_windows()
             .take(6)
             .zip(Piece::iter().skip(1))
-            .for_each(|((a, b), piece_kind)| {
-                let piece_color = rgb::Rgb::from(piece_kind);
-                let triangle = draw
-                    .tri()
-                    .color(piece_color.lighten(0.1))
-                    .points(a, b, a + b);
-                if self.turn.owns(piece_kind, self.mode) {
-                    triangle
-                        .stroke(piece_color.darken(0.2))
-                        .stroke_weight(0.005);
+            .map(|((a, b), piece_kind)| ([a, b, a + b], piece_kind))
+    }
+
+    /// Angle (counterclockwise from the x axis) at which the home triangles of the given player
+    /// point away from the center, on average
+    pub fn home_angle(turn: Turn, mode: Mode) -> Option<f32> {
+        let direction = Self::home_triangles()
+            .filter(|&(_, piece_kind)| turn.owns(piece_kind, mode))
+            .map(|([_, _, tip], _)| tip)
+            .fold(Vec2::ZERO, |sum, tip| sum + tip);
+        (direction != Vec2::ZERO).then(|| direction.y.atan2(direction.x))
+    }
+
+    /// The piece whose home the position lies in, or `None` in the central hexagon
+    pub fn region_of(position: IVec2) -> Piece {
+        Piece::iter()
+            .skip(1)
+            .find(|&piece| Self::home(piece).contains(&position))
+            .unwrap_or_default()
+    }
+
+    /// What is at the given position and which region it belongs to, e.g. for a tooltip
+    pub fn describe_cell(&self, position: IVec2) -> Option<String> {
+        let name = |piece| self.player_name(self.owner(piece));
+        let occupant = match self.get(&position)? {
+            Piece::None => "empty".to_string(),
+            piece => format!("{}'s piece", name(piece)),
+        };
+        let region = match Self::region_of(position) {
+            Piece::None => "center".to_string(),
+            piece => format!(
+                "home of {}, target of {}",
+                name(piece),
+                name(piece.opposite())
+            ),
+        };
+        Some(format!(
+            "{}: {occupant}\n{region}",
+            Self::cell_name(position)
+        ))
+    }
+
+    pub fn draw(&self, draw: &Draw, theme: &Theme, view: &View, scale: f32) {
+        self.draw_scene(draw, theme);
+        if view.labels {
+            self.draw_labels(draw, theme, view.pixel(scale), view.rotation());
+        }
+    }
+
+    fn draw_scene(&self, draw: &Draw, theme: &Theme) {
+        for shape in self.scene(theme).shapes {
+            match shape {
+                Shape::Polygon {
+                    points,
+                    fill,
+                    stroke: Some((color, width)),
+                } => {
+                    draw.polygon()
+                        .color(fill.srgba())
+                        .stroke(color.srgba())
+                        .stroke_weight(width)
+                        .points(points);
                 }
-            });
+                Shape::Polygon { points, fill, .. } => {
+                    draw.polygon().color(fill.srgba()).points(points);
+                }
+                Shape::Circle {
+                    center,
+                    radius,
+                    fill,
+                } => {
+                    draw.ellipse()
+                        .color(fill.srgba())
+                        .resolution(20.0)
+                        .xy(center)
+                        .radius(radius);
+                }
+                Shape::Polyline {
+                    points,
+                    width,
+                    color,
+                } => {
+                    draw.polyline()
+                        .weight(width)
+                        .color(color.srgba())
+                        .points(points);
+                }
+            }
+        }
+    }
+
+    /// Writes the name of every cell on top of it, kept upright however the view turns the board.
+    /// `pixel` is the size of a pixel on screen in board coordinates.
+    fn draw_labels(&self, draw: &Draw, theme: &Theme, pixel: f32, rotation: f32) {
+        let font_size = (Self::WIDTH / pixel * 0.8).clamp(6.0, 32.0) as u32;
+        for (pos, state) in self.backing.iter() {
+            let background: Paint = self.piece_color(theme, state).into();
+            let color = if background.luminance() > 0.5 {
+                BLACK
+            } else {
+                WHITE
+            };
+            draw.xy(Self::physical_position(&pos))
+                .rotate(-rotation)
+                .scale(pixel)
+                .text(&Self::cell_name(pos))
+                .font_size(font_size)
+                .color(color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use nannou::rand::{rngs::StdRng, SeedableRng};
+
+    use super::super::clock::TimeControl;
+    use super::*;
+
+    fn started(mode: Mode) -> Board {
+        let mut board = Board::default();
+        board.reset(mode);
+        board
+    }
+
+    #[test]
+    fn divide_finds_positive_multiples() {
+        assert_eq!(divide(ivec2(3, 0), IVec2::X), Some(3));
+        assert_eq!(divide(ivec2(0, -2), -IVec2::Y), Some(2));
+        assert_eq!(divide(ivec2(-4, 4), ivec2(-1, 1)), Some(4));
+        assert_eq!(divide(ivec2(-2, 0), IVec2::X), None);
+        assert_eq!(divide(ivec2(2, 1), IVec2::X), None);
+        assert_eq!(divide(ivec2(0, 2), IVec2::X), None);
+        assert_eq!(divide(IVec2::ZERO, IVec2::Y), None);
+    }
+
+    #[test]
+    fn cardinal_distance_follows_lines() {
+        let origin = ivec2(1, -2);
+        for cardinal in Board::cardinals() {
+            for n in 1..5 {
+                assert_eq!(
+                    Board::cardinal_distance(origin, origin + cardinal * n),
+                    Some((cardinal, n))
+                );
+            }
+        }
+        // the right diagonal does not connect neighbouring cells
+        assert_eq!(Board::cardinal_distance(IVec2::ZERO, ivec2(1, 1)), None);
+        assert_eq!(Board::cardinal_distance(IVec2::ZERO, ivec2(2, 1)), None);
+        assert_eq!(Board::cardinal_distance(origin, origin), None);
+    }
+
+    #[test]
+    fn regions_are_disjoint_triangles_on_the_board() {
+        let regions = [
+            Board::region_1().collect::<Vec<_>>(),
+            Board::region_2().collect(),
+            Board::region_3().collect(),
+            Board::region_4().collect(),
+            Board::region_5().collect(),
+            Board::region_6().collect(),
+        ];
+        let all = Board::all_positions().collect::<Vec<_>>();
+        assert_eq!(all.len(), cells::CELL_COUNT);
+
+        for (i, region) in regions.iter().enumerate() {
+            assert_eq!(region.len(), 10);
+            assert!(region.iter().all(|v| all.contains(v)));
+            assert!(region
+                .iter()
+                .all(|v| Board::hex_distance(IVec2::ZERO, *v) >= 5));
+            // opposite regions mirror each other through the center
+            let opposite = &regions[(i + 3) % 6];
+            assert!(region.iter().all(|v| opposite.contains(&-*v)));
+            for other in &regions[i + 1..] {
+                assert!(region.iter().all(|v| !other.contains(v)));
+            }
+        }
+    }
+
+    fn assert_region(region: impl Iterator<Item = IVec2>, expected: &[(i32, i32)]) {
+        let mut region = region.map(|v| (v.x, v.y)).collect::<Vec<_>>();
+        region.sort();
+        let mut expected = expected.to_vec();
+        expected.sort();
+        assert_eq!(region, expected);
+    }
+
+    #[test]
+    fn region_1_is_the_top_right_point() {
+        assert_region(
+            Board::region_1(),
+            &[
+                (4, 4),
+                (3, 4),
+                (4, 3),
+                (2, 4),
+                (3, 3),
+                (4, 2),
+                (1, 4),
+                (2, 3),
+                (3, 2),
+                (4, 1),
+            ],
+        );
+    }
+
+    #[test]
+    fn region_2_is_the_top_point() {
+        assert_region(
+            Board::region_2(),
+            &[
+                (-4, 8),
+                (-4, 7),
+                (-3, 7),
+                (-4, 6),
+                (-3, 6),
+                (-2, 6),
+                (-4, 5),
+                (-3, 5),
+                (-2, 5),
+                (-1, 5),
+            ],
+        );
+    }
+
+    #[test]
+    fn region_3_is_the_top_left_point() {
+        assert_region(
+            Board::region_3(),
+            &[
+                (-8, 4),
+                (-7, 4),
+                (-7, 3),
+                (-6, 4),
+                (-6, 3),
+                (-6, 2),
+                (-5, 4),
+                (-5, 3),
+                (-5, 2),
+                (-5, 1),
+            ],
+        );
+    }
+
+    #[test]
+    fn region_4_is_the_bottom_left_point() {
+        assert_region(
+            Board::region_4(),
+            &[
+                (-4, -4),
+                (-3, -4),
+                (-4, -3),
+                (-2, -4),
+                (-3, -3),
+                (-4, -2),
+                (-1, -4),
+                (-2, -3),
+                (-3, -2),
+                (-4, -1),
+            ],
+        );
+    }
+
+    #[test]
+    fn region_5_is_the_bottom_point() {
+        assert_region(
+            Board::region_5(),
+            &[
+                (4, -8),
+                (4, -7),
+                (3, -7),
+                (4, -6),
+                (3, -6),
+                (2, -6),
+                (4, -5),
+                (3, -5),
+                (2, -5),
+                (1, -5),
+            ],
+        );
+    }
+
+    #[test]
+    fn region_6_is_the_bottom_right_point() {
+        assert_region(
+            Board::region_6(),
+            &[
+                (8, -4),
+                (7, -4),
+                (7, -3),
+                (6, -4),
+                (6, -3),
+                (6, -2),
+                (5, -4),
+                (5, -3),
+                (5, -2),
+                (5, -1),
+            ],
+        );
+    }
+
+    #[test]
+    fn step_cannot_be_followed_by_jump() {
+        let mut board = started(Mode::Two);
+        board.move_piece(&ivec2(4, 1), &ivec2(0, 3));
+
+        assert!(board.try_push_path(ivec2(1, 4)));
+        assert!(board.try_push_path(ivec2(0, 4)));
+        assert!(!board.try_push_path(ivec2(0, 2)));
+        assert!(!board.try_push_path(ivec2(-1, 4)));
+    }
+
+    #[test]
+    fn legal_paths_match_first_moves() {
+        for mode in Mode::iter() {
+            let board = started(mode);
+            assert_eq!(board.legal_paths().len() as u64, board.perft(1));
+        }
+    }
+
+    #[test]
+    fn only_legal_paths_are_played() {
+        let mut board = started(Mode::Two);
+        let before = board.clone();
+        // a move off the lines of the board, and a move of the other player
+        let mut other = board.clone();
+        other.turn = Turn::Player2;
+        assert!(!board.play_path(&[ivec2(4, 1), IVec2::ZERO]));
+        assert!(!board.play_path(&other.legal_paths()[0]));
+        assert!(board == before);
+
+        let path = board.legal_paths().pop().unwrap();
+        assert!(board.play_path(&path));
+        assert_eq!(board.get(&path[0]), Some(Piece::None));
+        assert!(board.get(path.last().unwrap()).unwrap() != Piece::None);
+        assert_eq!(board.turn, Turn::Player2);
+        assert_eq!(
+            board.history,
+            [Move {
+                turn: Turn::Player1,
+                path
+            }]
+        );
+    }
+
+    #[test]
+    fn first_player_in_turn_order_wins() {
+        let mut board = started(Mode::Two);
+        // the first to finish wins whether or not the others play on, but without playing on
+        // finishers are found without having been placed
+        board.rules.play_on = false;
+        assert_eq!(board.winner(), None);
+
+        board.backing = Cells::empty();
+        for piece in Piece::iter().skip(1) {
+            board.fill_area(Board::target(piece).into_iter(), piece);
+        }
+        assert!(board.finished(Turn::Player1) && board.finished(Turn::Player2));
+        assert_eq!(board.winner(), Some(Turn::Player1));
+
+        board.forfeited.push(Turn::Player1);
+        assert_eq!(board.winner(), Some(Turn::Player2));
+
+        // the last player standing wins without finishing
+        let mut board = started(Mode::Three);
+        board.forfeited = vec![Turn::Player1, Turn::Player3];
+        assert_eq!(board.winner(), Some(Turn::Player2));
+    }
+
+    #[test]
+    fn spoiled_targets_count_as_reached_when_full() {
+        let mut board = started(Mode::Two);
+        board.rules.anti_spoiling = true;
+        board.backing = Cells::empty();
+        for piece in [Piece::Player1, Piece::Player2, Piece::Player6] {
+            board.fill_area(Board::target(piece).into_iter(), piece);
+        }
+        assert!(board.finished(Turn::Player1));
+
+        // Player2 sits in two targets of Player1, one of which Player1 fills up with a piece of
+        // another kind, leaving two pieces outside
+        let target = Board::target(Piece::Player1);
+        let other = Board::target(Piece::Player2)[0];
+        board.backing.set(target[0], Piece::Player3);
+        board.backing.set(target[1], Piece::Player2);
+        board.backing.set(other, Piece::Player3);
+        board.backing.set(IVec2::ZERO, Piece::Player1);
+        board.backing.set(IVec2::X, Piece::Player1);
+        assert!(board.finished(Turn::Player1));
+
+        board.rules.anti_spoiling = false;
+        assert!(!board.finished(Turn::Player1));
+        board.rules.anti_spoiling = true;
+        board.backing.set(target[0], Piece::None);
+        assert!(!board.finished(Turn::Player1));
+    }
+
+    #[test]
+    fn cells_off_the_board_are_never_legal() {
+        let mut board = started(Mode::Two);
+        assert!(!board.play_path(&[ivec2(4, 4), ivec2(-8, -8)]));
+        let path = board.legal_paths().remove(0);
+        assert!(!board.play_path(&[path[0], ivec2(-8, -8)]));
+    }
+
+    #[test]
+    fn targets_full_of_opponents_are_not_reached() {
+        for mode in Mode::iter() {
+            let mut board = started(mode);
+            board.rules.anti_spoiling = true;
+            assert!(mode.turns().all(|turn| !board.finished(turn)), "{mode:?}");
+
+            let path = board.legal_paths().remove(0);
+            assert!(board.play_path(&path));
+            assert_eq!(board.outcome(), None, "{mode:?}");
+            assert!(board.placings.is_empty(), "{mode:?}");
+            assert!(mode.turns().all(|turn| !board.finished(turn)), "{mode:?}");
+        }
+    }
+
+    /// The hash of the position worked out from scratch rather than piece by piece
+    fn hash_from_scratch(board: &Board) -> u64 {
+        board
+            .pieces()
+            .fold(zobrist::turn_key(board.turn), |hash, (position, piece)| {
+                hash ^ zobrist::piece_key(Cells::index(position).unwrap(), piece)
+            })
+    }
+
+    #[test]
+    fn made_moves_keep_the_hash_and_are_taken_back() {
+        for mode in Mode::iter() {
+            let mut rng = StdRng::seed_from_u64(0);
+            let mut board = started(mode);
+            for _ in 0..40 {
+                let before = board.clone();
+                for path in board.legal_paths() {
+                    let made = board.make_move(&path);
+                    assert_eq!(board.hash(), hash_from_scratch(&board));
+                    board.unmake_move(made);
+                    assert!(board == before);
+                }
+                let made = board.make_move(&[]);
+                assert_eq!(board.hash(), hash_from_scratch(&board));
+                board.unmake_move(made);
+                assert!(board == before);
+
+                let path = board.legal_paths().choose(&mut rng).cloned().unwrap();
+                assert!(board.play_path(&path));
+                assert_eq!(board.hash(), hash_from_scratch(&board));
+            }
+            while board.undo_move().is_some() {
+                assert_eq!(board.hash(), hash_from_scratch(&board));
+            }
+            assert!(board.backing == started(mode).backing);
+        }
+    }
+
+    fn assert_invariants(board: &Board) {
+        // every cell holds exactly one thing, and the bitboards agree with the cells
+        let mut seen = 0u128;
+        for (pos, piece) in board.backing.iter() {
+            let i = Cells::index(pos).unwrap();
+            assert_eq!(seen & 1 << i, 0, "{pos} visited twice");
+            seen |= 1 << i;
+            assert_ne!(board.backing.bitboard(piece) & 1 << i, 0);
+        }
+        let total = Piece::iter().fold(0u128, |union, piece| {
+            let bitboard = board.backing.bitboard(piece);
+            assert_eq!(union & bitboard, 0, "{piece} overlaps another piece");
+            union | bitboard
+        });
+        assert_eq!(total, seen);
+
+        for piece in Piece::iter().skip(1) {
+            assert_eq!(board.pieces().filter(|(_, p)| *p == piece).count(), 10);
+        }
+
+        assert!(board
+            .pieces()
+            .any(|(_, piece)| board.turn.owns(piece, board.mode)));
+
+        let loaded = ron::from_str::<Board>(&ron::to_string(board).unwrap()).unwrap();
+        assert!(loaded == *board, "board changed after saving and loading");
+        assert_eq!(loaded.hash(), board.hash());
+    }
+
+    #[test]
+    fn random_games_keep_invariants() {
+        for mode in Mode::iter() {
+            for seed in 0..8 {
+                let mut rng = StdRng::seed_from_u64(seed);
+                let mut board = started(mode);
+                assert_invariants(&board);
+                for _ in 0..150 {
+                    if board.outcome().is_some() {
+                        break;
+                    }
+                    match board.legal_paths().choose(&mut rng).cloned() {
+                        Some(path) => assert!(board.play_path(&path)),
+                        None => board.skip_turn(),
+                    }
+                    assert_invariants(&board);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn perft_counts_a_hand_checked_position() {
+        let mut board = started(Mode::Two);
+        board.backing = Cells::empty();
+        board.backing.set(IVec2::ZERO, Piece::Player1);
+        board.backing.set(IVec2::X, Piece::Player4);
+        // five steps and a jump to (2, 0)
+        assert_eq!(board.perft(1), 6);
+        // whichever of those is played, the other piece has six moves too: either six steps, or
+        // five steps and a jump when the first piece lands next to it
+        assert_eq!(board.perft(2), 36);
+
+        // a second piece to jump over chains the jump on to (4, 0)
+        board.backing.set(ivec2(3, 0), Piece::Player4);
+        assert_eq!(board.perft(1), 7);
+    }
+
+    /// At the start, every point of ten pieces has 14 moves: the four pieces in front step to two
+    /// cells each, and the three behind them jump to two cells each. The points do not get in each
+    /// other's way, so a player with two or three points has 28 or 42 moves. The deeper counts were
+    /// recorded from this move generator to catch changes to it, and are not checked by hand.
+    #[test]
+    fn perft_from_start() {
+        let expected = [
+            (Mode::Two, [42, 1770, 92288]),
+            (Mode::Three, [28, 787, 22204]),
+            (Mode::Six, [14, 199, 2828]),
+        ];
+        for (mode, counts) in expected {
+            let board = started(mode);
+            for (depth, count) in (1..).zip(counts) {
+                assert_eq!(board.perft(depth), count, "{mode} players at depth {depth}");
+            }
+        }
+    }
+
+    /// A three player game where Player1 is one step away from filling their targets
+    fn nearly_finished(play_on: bool) -> Board {
+        let mut board = started(Mode::Three);
+        board.rules.play_on = play_on;
+        board.backing = Cells::empty();
+        board.fill_area(Board::target(Piece::Player1).into_iter(), Piece::Player1);
+        board.fill_area(Board::target(Piece::Player2).into_iter(), Piece::Player2);
+        board.fill_area(Board::region_3(), Piece::Player3);
+        board.fill_area(Board::region_6(), Piece::Player6);
+        board.move_piece(&ivec2(-1, -4), &ivec2(-1, -3));
+        board
+    }
+
+    #[test]
+    fn first_finisher_wins_unless_playing_on() {
+        let mut board = nearly_finished(false);
+        assert!(board.play_path(&[ivec2(-1, -3), ivec2(-1, -4)]));
+        assert_eq!(board.outcome(), Some(Outcome::Won(Turn::Player1)));
+
+        let mut board = nearly_finished(true);
+        assert!(board.play_path(&[ivec2(-1, -3), ivec2(-1, -4)]));
+        assert_eq!(board.placings, [Turn::Player1]);
+        assert_eq!(board.outcome(), None);
+        assert_eq!(board.turn, Turn::Player2);
+
+        let standings = board.standings();
+        assert_eq!(standings[0].turn, Turn::Player1);
+        assert!(standings[0].finished);
+        assert!(standings[1..]
+            .iter()
+            .all(|standing| standing.score < standings[0].score));
+
+        // finished players are skipped
+        board.skip_turn();
+        board.skip_turn();
+        assert_eq!(board.turn, Turn::Player2);
+
+        board.forfeited.push(Turn::Player3);
+        assert_eq!(board.outcome(), Some(Outcome::Won(Turn::Player1)));
+    }
+
+    #[test]
+    fn undoing_moves_restores_the_game_before_them() {
+        let mut board = Board::default();
+        board.clocks.control = Some(TimeControl {
+            increment: Duration::from_secs(2),
+            ..TimeControl::default()
+        });
+        board.reset(Mode::Three);
+
+        let mut before = Vec::new();
+        for _ in 0..3 {
+            board.tick(Duration::from_secs(3));
+            before.push(board.clone());
+            let step = board.legal_paths().into_iter().find(|path| path.len() == 2);
+            assert!(board.play_path(&step.unwrap()));
+            if before.len() == 1 {
+                // Player2 runs out of time and forfeits
+                board.tick(Duration::from_secs(5 * 60));
+            } else {
+                board.skip_turn();
+            }
+        }
+        assert_eq!(board.forfeited, [Turn::Player2]);
+        assert_eq!(board.history.len(), 3);
+
+        while let Some(board_before) = before.pop() {
+            board.undo_move();
+            assert!(board == board_before);
+        }
+        assert!(board.forfeited.is_empty());
+        assert_eq!(
+            board.clocks.remaining(Turn::Player1),
+            Some(Duration::from_secs(5 * 60 - 3))
+        );
+    }
+
+    /// A game of the mode under the time control, with the clocks started
+    fn timed(mode: Mode, control: TimeControl) -> Board {
+        let mut board = Board::default();
+        board.clocks.control = Some(control);
+        board.reset(mode);
+        board
     }
 
-    fn draw_pieces(&self, draw: &Draw) {
-        for (pos, state) in &self.backing {
-            draw.ellipse()
-                .color(rgb::Rgb::from(*state))
-                .resolution(20.0)
-                .xy(Self::physical_position(pos))
-                .radius(Self::WIDTH)
-                .finish();
+    #[test]
+    fn flags_fall_as_the_time_control_says() {
+        let out_of_time = Duration::from_secs(5 * 60);
+
+        let mut board = timed(Mode::Three, TimeControl::default());
+        board.tick(out_of_time - Duration::from_secs(1));
+        assert_eq!(board.turn, Turn::Player1);
+        board.tick(Duration::from_secs(1));
+        assert_eq!(board.forfeited, [Turn::Player1]);
+        assert_eq!(board.turn, Turn::Player2);
+        assert!(board.history.is_empty());
+        assert_eq!(board.outcome(), None);
+
+        let mut board = timed(
+            Mode::Three,
+            TimeControl {
+                flag_fall: FlagFall::Pass,
+                ..TimeControl::default()
+            },
+        );
+        board.tick(out_of_time);
+        assert_eq!(board.turn, Turn::Player2);
+        assert!(board.forfeited.is_empty() && board.history.is_empty());
+        assert_eq!(board.clocks.remaining(Turn::Player1), Some(Duration::ZERO));
+        assert_eq!(board.outcome(), None);
+
+        let mut board = timed(
+            Mode::Three,
+            TimeControl {
+                flag_fall: FlagFall::RandomMove,
+                ..TimeControl::default()
+            },
+        );
+        let start = board.clone();
+        board.tick(out_of_time);
+        assert_eq!(board.turn, Turn::Player2);
+        assert!(board.forfeited.is_empty());
+        assert_eq!(board.history.len(), 1);
+        assert_eq!(board.history[0].turn, Turn::Player1);
+        assert!(start.clone().play_path(&board.history[0].path));
+        assert_eq!(board.outcome(), None);
+    }
+
+    #[test]
+    fn moves_may_take_no_longer_than_the_limit() {
+        let mut board = timed(
+            Mode::Two,
+            TimeControl {
+                per_move: Some(Duration::from_secs(10)),
+                flag_fall: FlagFall::Pass,
+                ..TimeControl::default()
+            },
+        );
+        let second = board.mode.turns().nth(1).unwrap();
+        board.tick(Duration::from_secs(9));
+        assert_eq!(board.turn, Turn::Player1);
+        board.tick(Duration::from_secs(1));
+        assert_eq!(board.turn, second);
+        assert!(board.history.is_empty());
+        assert_eq!(
+            board.clocks.remaining(Turn::Player1),
+            Some(Duration::from_secs(5 * 60 - 10))
+        );
+
+        // the next move is timed from the start, and a move played in time resets it too
+        board.tick(Duration::from_secs(9));
+        assert_eq!(board.turn, second);
+        let step = board.legal_paths().remove(0);
+        assert!(board.play_path(&step));
+        board.tick(Duration::from_secs(9));
+        assert_eq!(board.turn, Turn::Player1);
+
+        // losing on the move limit forfeits like running out of time
+        let mut board = timed(
+            Mode::Two,
+            TimeControl {
+                per_move: Some(Duration::from_secs(10)),
+                ..TimeControl::default()
+            },
+        );
+        board.tick(Duration::from_secs(10));
+        assert_eq!(board.forfeited, [Turn::Player1]);
+        assert_eq!(board.outcome(), Some(Outcome::Won(second)));
+    }
+
+    #[test]
+    fn replay_shows_every_move() {
+        let mut board = started(Mode::Two);
+        let paths = [
+            [ivec2(4, 1), ivec2(3, 1)],
+            [ivec2(-4, -1), ivec2(-3, -1)],
+            [ivec2(3, 1), ivec2(2, 1)],
+        ];
+        for path in paths {
+            assert!(board.play_path(&path));
+        }
+        board.skip_turn();
+
+        let replay = board.replay();
+        assert_eq!(replay.len(), paths.len() + 1);
+        assert!(replay[0].history.is_empty() && replay[0].path.is_empty());
+        for (i, position) in replay.iter().enumerate().skip(1) {
+            assert_eq!(position.history.len(), i);
+            assert_eq!(position.path, paths[i - 1]);
+        }
+        // the last position is the game as it stands, the pass included
+        assert_eq!(replay[3].hash(), board.hash());
+        assert_eq!(replay[3].turn, Turn::Player1);
+
+        // games saved without what the moves changed still replay their pieces
+        board.undo.clear();
+        let replay = board.replay();
+        assert!(replay[0].backing == started(Mode::Two).backing);
+    }
+
+    /// Moves a piece of each player of a two player game out and back, returning to the start
+    fn shuffle_back_and_forth(board: &mut Board) {
+        for path in [
+            [ivec2(4, 1), ivec2(3, 1)],
+            [ivec2(-4, -1), ivec2(-3, -1)],
+            [ivec2(3, 1), ivec2(4, 1)],
+            [ivec2(-3, -1), ivec2(-4, -1)],
+        ] {
+            assert!(board.play_path(&path));
         }
     }
 
-    fn draw_path(&self, draw: &Draw) {
-        let highlight_color = Alpha::<Rgb<_>, _>::new(0.0, 0.0, 0.0, 0.5);
+    #[test]
+    fn repeated_positions_end_the_game() {
+        let mut board = started(Mode::Two);
+        board.rules.repetition = Some(3);
+        shuffle_back_and_forth(&mut board);
+        assert_eq!(board.outcome(), None);
+        shuffle_back_and_forth(&mut board);
+        assert_eq!(board.outcome(), Some(Outcome::Draw));
+
+        // taking a move back forgets the position it reached
+        board.undo_move();
+        assert_eq!(board.outcome(), None);
+
+        // repetitions are still counted after saving and loading
+        board.play_path(&[ivec2(-3, -1), ivec2(-4, -1)]);
+        let loaded = ron::from_str::<Board>(&ron::to_string(&board).unwrap()).unwrap();
+        assert_eq!(loaded.outcome(), Some(Outcome::Draw));
+    }
+
+    #[test]
+    fn move_limit_ends_the_game() {
+        let mut board = started(Mode::Two);
+        board.rules.move_limit = Some(3);
+        assert!(board.play_path(&[ivec2(4, 1), ivec2(3, 1)]));
+        assert!(board.play_path(&[ivec2(-4, -1), ivec2(-3, -1)]));
+        assert_eq!(board.outcome(), None);
+        // passing is not a move
+        board.skip_turn();
+        board.skip_turn();
+        assert_eq!(board.outcome(), None);
+        assert!(board.play_path(&[ivec2(3, 1), ivec2(2, 1)]));
+        assert_eq!(board.outcome(), Some(Outcome::Draw));
+    }
+
+    #[test]
+    fn limit_result_scores_progress() {
+        let mut board = started(Mode::Two);
+        board.rules.move_limit = Some(1);
+        board.rules.limit_result = LimitResult::Progress;
+        assert!(board.play_path(&[ivec2(4, 1), ivec2(3, 1)]));
+        assert_eq!(board.outcome(), Some(Outcome::WonOnProgress(Turn::Player1)));
+
+        // level players draw
+        board.rules.move_limit = Some(2);
+        assert!(board.play_path(&[ivec2(-4, -1), ivec2(-3, -1)]));
+        assert_eq!(board.outcome(), Some(Outcome::Draw));
+
+        // forfeited players are not scored
+        board.forfeited.push(Turn::Player1);
+        assert_eq!(board.outcome(), Some(Outcome::Won(Turn::Player2)));
+    }
 
-        for point in &self.path {
-            draw.ellipse()
-                .color(highlight_color)
-                .resolution(20.0)
-                .radius(Self::HIGHLIGHT_WIDTH)
-                .xy(Self::physical_position(point));
+    #[test]
+    fn nobody_scores_at_the_start() {
+        for mode in Mode::iter() {
+            let board = started(mode);
+            assert!(board.rules.play_on);
+            assert!(board.standings().iter().all(|standing| standing.score == 0));
         }
-        for (p1, p2) in self.path.iter().tuple_windows() {
-            draw.line()
-                .start(Board::physical_position(p1))
-                .end(Board::physical_position(p2))
-                .weight(2.0 * Self::HIGHLIGHT_WIDTH)
-                .color(highlight_color);
+    }
+
+    #[test]
+    fn edited_positions_are_checked_before_playing() {
+        let mut board = started(Mode::Two);
+        assert!(board.problems().is_empty());
+
+        let cell = ivec2(0, 0);
+        board.paint(cell, editing::Brush::Cycle);
+        assert_eq!(board.get(&cell), Some(Piece::Player1));
+        assert_eq!(board.problems().len(), 1, "one piece too many");
+        board.paint(cell, editing::Brush::Paint(Piece::None));
+        assert!(board.problems().is_empty());
+
+        board.clear();
+        board.paint(cell, editing::Brush::Paint(Piece::Player4));
+        assert_eq!(board.problems(), ["Player1 has no pieces"]);
+    }
+
+    #[test]
+    fn races_are_solved_exactly() {
+        let mut board = started(Mode::Two);
+        assert!(!board.is_race());
+
+        board.backing = Cells::empty();
+        for piece in Piece::iter().skip(1) {
+            board.fill_area(Board::target(piece).into_iter(), piece);
         }
+        // Player1 is a step short of finishing, Player2 two steps
+        board.move_piece(&ivec2(-1, -4), &ivec2(-1, -3));
+        board.move_piece(&ivec2(1, 4), &ivec2(1, 3));
+        board.move_piece(&ivec2(4, 1), &ivec2(3, 1));
+        assert!(board.is_race());
+
+        let race = board
+            .race(endgame::RaceBudget {
+                moves: 10,
+                nodes: 10_000,
+            })
+            .unwrap();
+        assert_eq!(race.moves, [(Turn::Player1, 1), (Turn::Player2, 2)]);
+        assert_eq!(race.winner(), Turn::Player1);
+        assert!(board.play_path(&race.line[0]));
+        assert_eq!(board.outcome(), Some(Outcome::Won(Turn::Player1)));
     }
 }
@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+
+use nannou::prelude::*;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use strum::IntoEnumIterator;
+
+use super::{zobrist, Board, Piece};
+
+pub const CELL_COUNT: usize = 121;
+const OFF_BOARD: u8 = u8::MAX;
+
+static LAYOUT: Lazy<Layout> = Lazy::new(Layout::new);
+
+/// Gives every cell of the board a fixed index, and works out ahead of time which cells neighbour
+/// each other and which jumps exist, so that nothing has to be hashed during move generation.
+struct Layout {
+    positions: Vec<IVec2>,
+    /// Index of each position, offset by 8 in both directions, or `OFF_BOARD`
+    indices: [[u8; 17]; 17],
+    /// For each cell and cardinal, the index of the adjacent cell
+    neighbours: Vec<[Option<usize>; 6]>,
+    /// For each cell and cardinal, the index of the cell jumped over and of the landing cell
+    jumps: Vec<[Option<(usize, usize)>; 6]>,
+    /// One mask of home cells for each kind of piece
+    homes: [u128; 7],
+}
+
+impl Layout {
+    fn new() -> Self {
+        let mut positions = Board::all_positions().collect::<Vec<_>>();
+        positions.sort_by_key(|v| (v.y, v.x));
+        assert_eq!(positions.len(), CELL_COUNT);
+
+        let mut indices = [[OFF_BOARD; 17]; 17];
+        for (i, v) in positions.iter().enumerate() {
+            indices[(v.x + 8) as usize][(v.y + 8) as usize] = i as u8;
+        }
+        let index = |v: IVec2| {
+            let (x, y) = ((v.x + 8) as usize, (v.y + 8) as usize);
+            (x < 17 && y < 17 && indices[x][y] != OFF_BOARD).then(|| indices[x][y] as usize)
+        };
+
+        let neighbours = positions
+            .iter()
+            .map(|&v| Board::cardinals().map(|cardinal| index(v + cardinal)))
+            .collect();
+        let jumps = positions
+            .iter()
+            .map(|&v| {
+                Board::cardinals().map(|cardinal| index(v + cardinal).zip(index(v + cardinal * 2)))
+            })
+            .collect();
+
+        let mut homes = [0; 7];
+        for piece in Piece::iter() {
+            homes[piece as usize] = Board::home(piece)
+                .into_iter()
+                .filter_map(index)
+                .fold(0, |mask, i| mask | 1 << i);
+        }
+
+        Self {
+            positions,
+            indices,
+            neighbours,
+            jumps,
+            homes,
+        }
+    }
+
+    fn index(&self, v: IVec2) -> Option<usize> {
+        let (x, y) = ((v.x + 8) as usize, (v.y + 8) as usize);
+        let index = *self.indices.get(x)?.get(y)?;
+        (index != OFF_BOARD).then_some(index as usize)
+    }
+}
+
+/// Iterates over the indices of the set bits of a bitboard
+pub fn indices(mut bitboard: u128) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        (bitboard != 0).then(|| {
+            let i = bitboard.trailing_zeros() as usize;
+            bitboard &= bitboard - 1;
+            i
+        })
+    })
+}
+
+/// The contents of every cell of the board. Pieces are stored both per cell and as one bitboard
+/// per kind of piece (empty cells included), which are kept in sync by `set` along with the
+/// Zobrist hash of the placement.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Cells {
+    pieces: [Piece; CELL_COUNT],
+    bitboards: [u128; 7],
+    hash: u64,
+}
+
+impl Cells {
+    /// A board without any pieces on it
+    pub fn empty() -> Self {
+        let mut bitboards = [0; 7];
+        bitboards[Piece::None as usize] = u128::MAX >> (128 - CELL_COUNT);
+        Self {
+            pieces: [Piece::None; CELL_COUNT],
+            bitboards,
+            hash: 0,
+        }
+    }
+
+    pub fn index(position: IVec2) -> Option<usize> {
+        LAYOUT.index(position)
+    }
+
+    pub fn position(index: usize) -> IVec2 {
+        LAYOUT.positions[index]
+    }
+
+    pub fn neighbours(index: usize) -> [Option<usize>; 6] {
+        LAYOUT.neighbours[index]
+    }
+
+    /// Pairs of the cell jumped over and the landing cell, for each cardinal
+    pub fn jumps(index: usize) -> [Option<(usize, usize)>; 6] {
+        LAYOUT.jumps[index]
+    }
+
+    /// Mask of the cells a kind of piece starts in
+    pub fn home_mask(piece: Piece) -> u128 {
+        LAYOUT.homes[piece as usize]
+    }
+
+    pub fn contains(&self, position: IVec2) -> bool {
+        Self::index(position).is_some()
+    }
+
+    pub fn get(&self, position: IVec2) -> Option<Piece> {
+        Self::index(position).map(|i| self.pieces[i])
+    }
+
+    pub fn at(&self, index: usize) -> Piece {
+        self.pieces[index]
+    }
+
+    /// Places the piece on the cell. Panics if the position is not on the board.
+    pub fn set(&mut self, position: IVec2, piece: Piece) {
+        self.set_at(Self::index(position).unwrap(), piece);
+    }
+
+    pub fn set_at(&mut self, index: usize, piece: Piece) {
+        let bit = 1 << index;
+        self.bitboards[self.pieces[index] as usize] &= !bit;
+        self.bitboards[piece as usize] |= bit;
+        self.hash ^=
+            zobrist::piece_key(index, self.pieces[index]) ^ zobrist::piece_key(index, piece);
+        self.pieces[index] = piece;
+    }
+
+    /// The cells holding the given kind of piece, or the empty cells for `Piece::None`
+    pub fn bitboard(&self, piece: Piece) -> u128 {
+        self.bitboards[piece as usize]
+    }
+
+    /// Zobrist hash of where the pieces are, not including whose turn it is
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (IVec2, Piece)> + '_ {
+        LAYOUT
+            .positions
+            .iter()
+            .copied()
+            .zip(self.pieces.iter().copied())
+    }
+}
+
+/// Stored as a map from position to piece, so that games saved before the board was indexed still
+/// load
+impl Serialize for Cells {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for Cells {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let map = HashMap::<IVec2, Piece>::deserialize(deserializer)?;
+        let mut cells = Cells::empty();
+        for (position, piece) in map {
+            if let Some(i) = Self::index(position) {
+                cells.set_at(i, piece);
+            }
+        }
+        Ok(cells)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bitboards_and_hash_follow_the_pieces() {
+        let mut cells = Cells::empty();
+        assert_eq!(
+            cells.bitboard(Piece::None).count_ones() as usize,
+            CELL_COUNT
+        );
+
+        let (a, b) = (ivec2(0, 0), ivec2(-4, -4));
+        cells.set(a, Piece::Player1);
+        cells.set(b, Piece::Player1);
+        cells.set(b, Piece::Player4);
+        let (i, j) = (Cells::index(a).unwrap(), Cells::index(b).unwrap());
+        assert_eq!(cells.bitboard(Piece::Player1), 1 << i);
+        assert_eq!(cells.bitboard(Piece::Player4), 1 << j);
+        assert_eq!(
+            cells.bitboard(Piece::None).count_ones() as usize,
+            CELL_COUNT - 2
+        );
+        // cells are indexed row by row, from the bottom
+        assert_eq!(
+            indices(cells.bitboard(Piece::Player1) | 1 << j).collect::<Vec<_>>(),
+            [j, i]
+        );
+        assert_eq!(cells.get(b), Some(Piece::Player4));
+        assert_eq!(cells.at(i), Piece::Player1);
+        assert_ne!(cells.hash(), 0);
+
+        cells.set(a, Piece::None);
+        cells.set(b, Piece::None);
+        assert!(cells == Cells::empty());
+        assert_eq!(cells.hash(), 0);
+    }
+
+    #[test]
+    fn neighbours_and_jumps_follow_the_cardinals() {
+        for i in 0..CELL_COUNT {
+            let position = Cells::position(i);
+            assert_eq!(Cells::index(position), Some(i));
+            for (k, cardinal) in Board::cardinals().into_iter().enumerate() {
+                assert_eq!(Cells::neighbours(i)[k], Cells::index(position + cardinal));
+                let jump =
+                    Cells::index(position + cardinal).zip(Cells::index(position + cardinal * 2));
+                assert_eq!(Cells::jumps(i)[k], jump);
+            }
+        }
+        assert_eq!(Cells::index(ivec2(8, 8)), None);
+        assert_eq!(Cells::index(ivec2(9, 0)), None);
+    }
+
+    #[test]
+    fn cells_load_from_saved_maps() {
+        let mut board = Board::default();
+        board.reset(super::super::Mode::Three);
+        let cells = board.backing.clone();
+        let loaded = ron::from_str::<Cells>(&ron::to_string(&cells).unwrap()).unwrap();
+        assert!(loaded == cells);
+
+        // games saved before the board was indexed stored a map of every position
+        let map = cells.iter().collect::<HashMap<_, _>>();
+        let loaded = ron::from_str::<Cells>(&ron::to_string(&map).unwrap()).unwrap();
+        assert!(loaded == cells);
+        assert_eq!(loaded.hash(), cells.hash());
+    }
+}
@@ -0,0 +1,70 @@
+//! Setting up positions by hand, to play from them or to save them as test scenarios
+
+use nannou::prelude::*;
+use strum::IntoEnumIterator;
+
+use super::{super::player::Piece, cells::Cells, Board};
+
+/// What clicking a cell does while the position is being edited
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub enum Brush {
+    /// Puts the next kind of piece on the cell, going back to empty after the last one
+    #[default]
+    Cycle,
+    Paint(Piece),
+}
+
+impl Board {
+    /// Changes the cell at the given position as the brush says, dropping the current path
+    pub fn paint(&mut self, position: IVec2, brush: Brush) {
+        let piece = match (brush, self.backing.get(position)) {
+            (_, None) => return,
+            (Brush::Paint(piece), _) => piece,
+            (Brush::Cycle, Some(current)) => Piece::iter()
+                .cycle()
+                .skip_while(|&piece| piece != current)
+                .nth(1)
+                .unwrap(),
+        };
+        self.path.clear();
+        self.backing.set(position, piece);
+    }
+
+    /// Takes every piece off the board
+    pub fn clear(&mut self) {
+        self.path.clear();
+        self.backing = Cells::empty();
+    }
+
+    /// Everything keeping the position from being played, e.g. a player without pieces. Empty if
+    /// the position is fine.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if !self.mode.turns().any(|turn| turn == self.turn) {
+            problems.push(format!(
+                "{} does not play in a game of {} players",
+                self.turn,
+                self.mode.players()
+            ));
+        }
+        for kind in Piece::iter().skip(1) {
+            let count = self.backing.bitboard(kind).count_ones();
+            if count > 10 {
+                problems.push(format!(
+                    "There are {count} of {kind}, more than their target holds"
+                ));
+            }
+        }
+        for turn in self.mode.turns() {
+            if !self.pieces().any(|(_, piece)| turn.owns(piece, self.mode)) {
+                problems.push(format!("{turn} has no pieces"));
+            } else if self.finished(turn) {
+                problems.push(format!("{turn} has already finished"));
+            }
+        }
+        if problems.is_empty() && self.legal_paths().is_empty() {
+            problems.push(format!("{} has no legal move", self.turn));
+        }
+        problems
+    }
+}
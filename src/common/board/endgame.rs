@@ -0,0 +1,220 @@
+//! Exact solving of races: positions of two player games in which the players' pieces have passed
+//! each other, so that each player only has to bring their own pieces home as fast as they can
+
+use std::collections::HashMap;
+
+use nannou::prelude::*;
+use strum::IntoEnumIterator;
+
+use super::{
+    super::player::{Mode, Piece, Turn},
+    cells::Cells,
+    Board,
+};
+
+/// How far the race solver may look: the longest finish it tries, and the most positions it visits
+/// for each player before giving up. Where it is used decides how long it may take.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct RaceBudget {
+    pub moves: usize,
+    pub nodes: usize,
+}
+
+impl RaceBudget {
+    /// For `ccheckers --race`, which has the machine to itself, so that it answers for all but the
+    /// most hopeless races, taking seconds if it must
+    pub const EXHAUSTIVE: Self = Self {
+        moves: 80,
+        nodes: 5_000_000,
+    };
+    /// For solving from the window, which waits for the answer, so it has to come within about a
+    /// second
+    pub const INTERACTIVE: Self = Self {
+        moves: 60,
+        nodes: 500_000,
+    };
+    /// For the search bot, which solves the race again before every move it plays, so it has to
+    /// answer in milliseconds
+    pub const SEARCH: Self = Self {
+        moves: 40,
+        nodes: 20_000,
+    };
+    /// For analysing games, which solves for every player after every move, so the least of all;
+    /// estimates stand in for races it gives up on
+    pub const ANALYSIS: Self = Self {
+        moves: 30,
+        nodes: 5_000,
+    };
+}
+
+/// The fewest moves each player of a race needs to finish
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Race {
+    /// The player to move first and their moves, then the other player and theirs
+    pub moves: [(Turn, usize); 2],
+    /// The quickest way to finish for the player to move
+    pub line: Vec<Vec<IVec2>>,
+}
+
+impl Race {
+    /// The player who finishes first, which is the player to move on equal numbers of moves
+    pub fn winner(&self) -> Turn {
+        let [(first, first_moves), (second, second_moves)] = self.moves;
+        if first_moves <= second_moves {
+            first
+        } else {
+            second
+        }
+    }
+
+    /// Who needs how many moves, who wins and how the player to move finishes quickest
+    pub fn summary(&self, board: &Board) -> String {
+        let [(first, first_moves), (second, second_moves)] = self.moves;
+        format!(
+            "{} needs {first_moves} moves, {} needs {second_moves}: {} wins the race.\n\
+             Quickest finish for {}: {}",
+            board.player_name(first),
+            board.player_name(second),
+            board.player_name(self.winner()),
+            board.player_name(first),
+            self.line
+                .iter()
+                .map(|path| Board::path_name(path))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+/// How far a cell is along the line from the homes of the second player of a two player game to
+/// those of the first, which their pieces travel in opposite directions
+fn progress(position: IVec2) -> i32 {
+    -(position.x + position.y)
+}
+
+/// Searches for the quickest way to finish with iterative deepening, giving up after visiting a
+/// set number of positions
+struct Solver {
+    turn: Turn,
+    /// Positions known not to finish within the given number of moves, or fewer
+    failed: HashMap<u64, usize>,
+    nodes_left: usize,
+}
+
+impl Solver {
+    fn finish_within(&mut self, board: &Board, moves: usize) -> Option<Vec<Vec<IVec2>>> {
+        if board.finished(self.turn) {
+            return Some(Vec::new());
+        }
+        let hash = board.hash();
+        if moves == 0
+            || board.moves_needed(self.turn) > moves
+            || self.nodes_left == 0
+            || self.failed.get(&hash).is_some_and(|&tried| tried >= moves)
+        {
+            return None;
+        }
+        self.nodes_left -= 1;
+
+        // try moves bringing pieces into their targets first, then those bringing them closest
+        let mut paths = board.legal_paths();
+        paths.sort_by_cached_key(|path| {
+            let (start, end) = (path[0], *path.last().unwrap());
+            let piece = board.get(&start).unwrap();
+            let target = Cells::home_mask(piece.opposite());
+            let inside = |position| Cells::index(position).is_some_and(|i| target & 1 << i != 0);
+            let tip = Board::target_tip(piece).unwrap();
+            (
+                inside(start) as i32 - inside(end) as i32,
+                Board::hex_distance(end, tip) - Board::hex_distance(start, tip),
+            )
+        });
+
+        for path in paths {
+            // the paths are legal already, and nobody else moves in between
+            let mut next = board.clone();
+            next.move_piece(&path[0], path.last().unwrap());
+            if let Some(mut rest) = self.finish_within(&next, moves - 1) {
+                rest.insert(0, path);
+                return Some(rest);
+            }
+        }
+        if self.nodes_left > 0 {
+            self.failed.insert(hash, moves);
+        }
+        None
+    }
+}
+
+impl Board {
+    /// Number of the player's pieces outside of their targets
+    pub fn pieces_outside(&self, turn: Turn) -> usize {
+        Piece::iter()
+            .filter(|&kind| turn.owns(kind, self.mode))
+            .map(|kind| {
+                (self.backing.bitboard(kind) & !Cells::home_mask(kind.opposite())).count_ones()
+                    as usize
+            })
+            .sum()
+    }
+
+    /// The least number of moves the player certainly needs to finish. Every move brings at most
+    /// one piece into its target, which only tells anything for certain without anti-spoiling.
+    fn moves_needed(&self, turn: Turn) -> usize {
+        match self.pieces_outside(turn) {
+            outside if self.rules.anti_spoiling => outside.min(1),
+            outside => outside,
+        }
+    }
+
+    /// True in a two player game once every piece of the first player is further along than every
+    /// piece of the second. From then on, neither player can jump over the other's pieces without
+    /// moving backwards, so the game is decided by who finishes in fewer moves.
+    pub fn is_race(&self) -> bool {
+        if self.mode != Mode::Two {
+            return false;
+        }
+        let along = |turn: Turn| {
+            self.pieces()
+                .filter(move |(_, piece)| turn.owns(*piece, self.mode))
+                .map(|(position, _)| progress(position))
+        };
+        match (along(Turn::Player1).min(), along(Turn::Player2).max()) {
+            (Some(first), Some(second)) => first > second,
+            _ => false,
+        }
+    }
+
+    /// The fewest moves, at most those of the budget, that bring all of the player's pieces into
+    /// their targets if nobody else moves, or None if there are more or the search visits more
+    /// positions than the budget allows before finding them
+    pub fn shortest_finish(&self, turn: Turn, budget: RaceBudget) -> Option<Vec<Vec<IVec2>>> {
+        let mut board = self.clone();
+        board.turn = turn;
+        board.start_from_here();
+
+        let mut solver = Solver {
+            turn,
+            failed: HashMap::new(),
+            nodes_left: budget.nodes,
+        };
+        (board.moves_needed(turn)..=budget.moves).find_map(|moves| match solver.nodes_left {
+            0 => None,
+            _ => solver.finish_within(&board, moves),
+        })
+    }
+
+    /// Solves the race for both players, if the position is one and neither search gives up
+    pub fn race(&self, budget: RaceBudget) -> Option<Race> {
+        if !self.is_race() {
+            return None;
+        }
+        let other = self.mode.next_turn(self.turn);
+        let line = self.shortest_finish(self.turn, budget)?;
+        let other_moves = self.shortest_finish(other, budget)?.len();
+        Some(Race {
+            moves: [(self.turn, line.len()), (other, other_moves)],
+            line,
+        })
+    }
+}
@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// The hash of every position reached in a game, in order, along with how many times each came
+/// up, so that repetitions are found without going through the whole game
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct Positions {
+    hashes: Vec<u64>,
+    counts: HashMap<u64, usize>,
+}
+
+impl Positions {
+    /// Forgets every position but the given starting one
+    pub fn restart(&mut self, hash: u64) {
+        self.hashes.clear();
+        self.counts.clear();
+        self.push(hash);
+    }
+
+    pub fn push(&mut self, hash: u64) {
+        self.hashes.push(hash);
+        *self.counts.entry(hash).or_default() += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<u64> {
+        let hash = self.hashes.pop()?;
+        if let Some(count) = self.counts.get_mut(&hash) {
+            *count -= 1;
+            if *count == 0 {
+                self.counts.remove(&hash);
+            }
+        }
+        Some(hash)
+    }
+
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    /// Forgets every position after the first `len`
+    pub fn truncate(&mut self, len: usize) {
+        while self.hashes.len() > len {
+            self.pop();
+        }
+    }
+
+    /// How many times the position came up
+    pub fn count(&self, hash: u64) -> usize {
+        self.counts.get(&hash).copied().unwrap_or_default()
+    }
+}
+
+/// Stored as the list of hashes, as it was before the counts were kept
+impl Serialize for Positions {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.hashes.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Positions {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut positions = Positions::default();
+        for hash in Vec::<u64>::deserialize(deserializer)? {
+            positions.push(hash);
+        }
+        Ok(positions)
+    }
+}
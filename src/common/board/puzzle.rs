@@ -0,0 +1,159 @@
+//! "Finish in N moves" puzzles: a position, the player who has to bring all of their pieces into
+//! their targets from it, and how many moves they may take. Everyone else passes.
+
+use nannou::prelude::*;
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+
+use super::{
+    super::player::{Mode, Piece, Turn},
+    cells::Cells,
+    endgame::RaceBudget,
+    Board,
+};
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Puzzle {
+    pub name: String,
+    pub mode: Mode,
+    /// The player solving the puzzle
+    pub turn: Turn,
+    /// The cells each kind of piece stands on, by name and separated by spaces, e.g.
+    /// `(Player1, "e5 f5 g5")`. The player solving the puzzle needs all 10 of each of their kinds.
+    pub pieces: Vec<(Piece, String)>,
+    /// Most moves the puzzle may be solved in
+    pub moves: usize,
+    /// A way of solving the puzzle, one move per entry written like `c5-e7-g9`
+    #[serde(default)]
+    pub solution: Vec<String>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Progress {
+    Solving { moves_left: usize },
+    Solved,
+    Failed,
+}
+
+/// The puzzles that come with the game
+pub fn bundled() -> Vec<Puzzle> {
+    ron::from_str(include_str!("puzzles.ron")).expect("bundled puzzles should load")
+}
+
+/// Reads a move written like `c5-e7-g9`
+pub fn parse_path(text: &str) -> Option<Vec<IVec2>> {
+    text.split('-').map(Board::parse_cell).collect()
+}
+
+impl Puzzle {
+    /// The starting position of the puzzle. The board's move limit ends the attempt once the
+    /// moves are used up.
+    pub fn board(&self) -> Result<Board, String> {
+        if !self.mode.turns().any(|turn| turn == self.turn) {
+            return Err(format!(
+                "{} does not play in a game of {} players",
+                self.turn,
+                self.mode.players()
+            ));
+        }
+
+        let mut board = Board::default();
+        board.reset(self.mode);
+        board.backing = Cells::empty();
+        for (piece, cells) in &self.pieces {
+            for name in cells.split_whitespace() {
+                let position = Board::parse_cell(name)
+                    .filter(|&position| board.backing.contains(position))
+                    .ok_or_else(|| format!("{name} is not a cell of the board"))?;
+                if board.get(&position) != Some(Piece::None) {
+                    return Err(format!("{name} holds more than one piece"));
+                }
+                board.backing.set(position, *piece);
+            }
+        }
+        board.turn = self.turn;
+        board.rules.move_limit = Some(self.moves);
+        board.start_from_here();
+
+        for kind in Piece::iter().filter(|&kind| self.turn.owns(kind, self.mode)) {
+            let count = board.backing.bitboard(kind).count_ones();
+            if count != 10 {
+                return Err(format!("{} has {count} of {kind} instead of 10", self.turn));
+            }
+        }
+        if let Some(turn) = self.mode.turns().find(|&turn| board.finished(turn)) {
+            return Err(format!("{turn} has already finished"));
+        }
+        Ok(board)
+    }
+
+    /// Passes for everyone else until it is the solver's move again
+    pub fn pass_others(&self, board: &mut Board) {
+        for _ in 0..self.mode.players() {
+            if board.turn == self.turn || board.outcome().is_some() {
+                break;
+            }
+            board.skip_turn();
+        }
+    }
+
+    pub fn progress(&self, board: &Board) -> Progress {
+        let made = board
+            .history
+            .iter()
+            .filter(|played| played.turn == self.turn)
+            .count();
+        if board.finished(self.turn) {
+            Progress::Solved
+        } else if made >= self.moves {
+            Progress::Failed
+        } else {
+            Progress::Solving {
+                moves_left: self.moves - made,
+            }
+        }
+    }
+
+    /// Checks that the puzzle sets up, that its solution (if it has one) solves it, and that it
+    /// can be solved at all, returning the fewest moves it can be solved in
+    pub fn verify(&self) -> Result<usize, String> {
+        let board = self.board()?;
+
+        if !self.solution.is_empty() {
+            let mut played = board.clone();
+            for text in &self.solution {
+                let path = parse_path(text).ok_or_else(|| format!("{text} is not a move"))?;
+                if !played.play_path(&path) {
+                    return Err(format!("{text} of the solution is not legal"));
+                }
+                self.pass_others(&mut played);
+            }
+            if self.progress(&played) != Progress::Solved {
+                return Err("the solution does not solve the puzzle".to_string());
+            }
+        }
+
+        board
+            .shortest_finish(
+                self.turn,
+                RaceBudget {
+                    moves: self.moves,
+                    nodes: usize::MAX,
+                },
+            )
+            .map(|paths| paths.len())
+            .ok_or_else(|| format!("there is no solution in {} moves", self.moves))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_puzzles_are_solvable_in_exactly_their_moves() {
+        for puzzle in bundled() {
+            assert_eq!(puzzle.verify(), Ok(puzzle.moves), "{}", puzzle.name);
+        }
+    }
+}
@@ -0,0 +1,266 @@
+//! A small CPU rasterizer for the board scene, so that images can be exported without a GPU
+
+use nannou::{
+    image::{
+        codecs::gif::{GifEncoder, Repeat},
+        Delay, DynamicImage, Frame, ImageOutputFormat, ImageResult, Rgba, RgbaImage,
+    },
+    prelude::*,
+};
+
+use super::{
+    super::theme::Theme,
+    scene::{Paint, Shape},
+    Board,
+};
+
+/// Subsamples per pixel along each axis, for antialiasing
+const SAMPLES: u32 = 4;
+
+fn distance_to_segment(p: Point2, a: Point2, b: Point2) -> f32 {
+    let ab = b - a;
+    let t = ((p - a).dot(ab) / ab.length_squared().max(f32::EPSILON)).clamp(0.0, 1.0);
+    p.distance(a + ab * t)
+}
+
+/// Even-odd test, which is all that is needed for the simple polygons of the board
+fn inside_polygon(p: Point2, points: &[Point2]) -> bool {
+    let mut inside = false;
+    for (a, b) in points.iter().zip(points.iter().cycle().skip(1)) {
+        if (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+fn bounds(points: impl Iterator<Item = Point2>, margin: f32) -> (Point2, Point2) {
+    points.fold(
+        (Point2::splat(f32::MAX), Point2::splat(f32::MIN)),
+        |(min, max), p| (min.min(p - margin), max.max(p + margin)),
+    )
+}
+
+struct Canvas {
+    image: RgbaImage,
+    /// Pixels per board unit
+    scale: f32,
+    extent: f32,
+}
+
+impl Canvas {
+    fn to_board(&self, x: f32, y: f32) -> Point2 {
+        pt2(x / self.scale - self.extent, self.extent - y / self.scale)
+    }
+
+    fn to_pixel(&self, p: Point2) -> (f32, f32) {
+        (
+            (p.x + self.extent) * self.scale,
+            (self.extent - p.y) * self.scale,
+        )
+    }
+
+    /// Blends the paint into every pixel within the bounds, weighted by how many of the pixel's
+    /// subsamples the shape covers
+    fn fill(
+        &mut self,
+        (min, max): (Point2, Point2),
+        paint: Paint,
+        covers: impl Fn(Point2) -> bool,
+    ) {
+        let (x0, y1) = self.to_pixel(min);
+        let (x1, y0) = self.to_pixel(max);
+        let (width, height) = self.image.dimensions();
+        let xs = (x0.floor().max(0.0) as u32)..(x1.ceil().max(0.0) as u32).min(width);
+        let ys = (y0.floor().max(0.0) as u32)..(y1.ceil().max(0.0) as u32).min(height);
+
+        for y in ys {
+            for x in xs.clone() {
+                let covered = (0..SAMPLES * SAMPLES)
+                    .filter(|i| {
+                        let dx = (i % SAMPLES) as f32 + 0.5;
+                        let dy = (i / SAMPLES) as f32 + 0.5;
+                        covers(self.to_board(
+                            x as f32 + dx / SAMPLES as f32,
+                            y as f32 + dy / SAMPLES as f32,
+                        ))
+                    })
+                    .count();
+                if covered > 0 {
+                    let alpha = paint.alpha * covered as f32 / (SAMPLES * SAMPLES) as f32;
+                    let pixel = self.image.get_pixel_mut(x, y);
+                    for (channel, &value) in pixel.0.iter_mut().zip(&paint.rgb) {
+                        *channel = (*channel as f32 * (1.0 - alpha) + value as f32 * alpha) as u8;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Board {
+    /// Rasterizes the board into a square image of the given size
+    pub fn to_image(&self, size: u32, theme: &Theme) -> RgbaImage {
+        let scene = self.scene(theme);
+        let [r, g, b] = scene.background.rgb;
+        let mut canvas = Canvas {
+            image: RgbaImage::from_pixel(size, size, Rgba([r, g, b, 255])),
+            scale: size as f32 / (2.0 * scene.extent),
+            extent: scene.extent,
+        };
+
+        for shape in &scene.shapes {
+            match shape {
+                Shape::Polygon {
+                    points,
+                    fill,
+                    stroke,
+                } => {
+                    canvas.fill(bounds(points.iter().copied(), 0.0), *fill, |p| {
+                        inside_polygon(p, points)
+                    });
+                    if let Some((color, width)) = *stroke {
+                        let edges = || points.iter().zip(points.iter().cycle().skip(1));
+                        canvas.fill(bounds(points.iter().copied(), width), color, |p| {
+                            edges().any(|(&a, &b)| distance_to_segment(p, a, b) <= width / 2.0)
+                        });
+                    }
+                }
+                Shape::Circle {
+                    center,
+                    radius,
+                    fill,
+                } => {
+                    canvas.fill(bounds(std::iter::once(*center), *radius), *fill, |p| {
+                        p.distance(*center) <= *radius
+                    });
+                }
+                Shape::Polyline {
+                    points,
+                    width,
+                    color,
+                } => {
+                    canvas.fill(bounds(points.iter().copied(), *width), *color, |p| {
+                        points
+                            .windows(2)
+                            .any(|w| distance_to_segment(p, w[0], w[1]) <= width / 2.0)
+                    });
+                }
+            }
+        }
+
+        canvas.image
+    }
+
+    /// The rasterized board, encoded as PNG
+    pub fn to_png(&self, size: u32, theme: &Theme) -> ImageResult<Vec<u8>> {
+        let mut png = Vec::new();
+        DynamicImage::ImageRgba8(self.to_image(size, theme))
+            .write_to(&mut png, ImageOutputFormat::Png)?;
+        Ok(png)
+    }
+
+    /// An animated GIF of every position of the game so far, showing each move as it is played,
+    /// looping forever
+    pub fn replay_gif(&self, size: u32, frame_ms: u32, theme: &Theme) -> ImageResult<Vec<u8>> {
+        let mut gif = Vec::new();
+        {
+            let mut encoder = GifEncoder::new(&mut gif);
+            encoder.set_repeat(Repeat::Infinite)?;
+            encoder.encode_frames(self.replay().iter().map(|board| {
+                Frame::from_parts(
+                    board.to_image(size, theme),
+                    0,
+                    0,
+                    Delay::from_numer_denom_ms(frame_ms, 1),
+                )
+            }))?;
+        }
+        Ok(gif)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nannou::image::{self, codecs::gif::GifDecoder, AnimationDecoder};
+
+    use super::super::{cells::Cells, Mode, Piece};
+    use super::*;
+
+    fn one_piece() -> Board {
+        let mut board = Board::default();
+        board.reset(Mode::Two);
+        board.backing = Cells::empty();
+        board.backing.set(IVec2::ZERO, Piece::Player1);
+        board
+    }
+
+    #[test]
+    fn polygons_and_segments_cover_the_right_points() {
+        let square = [pt2(0.0, 0.0), pt2(1.0, 0.0), pt2(1.0, 1.0), pt2(0.0, 1.0)];
+        assert!(inside_polygon(pt2(0.5, 0.5), &square));
+        assert!(!inside_polygon(pt2(1.5, 0.5), &square));
+        assert!(!inside_polygon(pt2(0.5, -0.5), &square));
+
+        let (a, b) = (pt2(0.0, 0.0), pt2(2.0, 0.0));
+        assert_eq!(distance_to_segment(pt2(1.0, 1.0), a, b), 1.0);
+        assert_eq!(distance_to_segment(pt2(3.0, 0.0), a, b), 1.0);
+        assert_eq!(distance_to_segment(pt2(1.0, 0.0), a, a), 1.0);
+    }
+
+    #[test]
+    fn image_shows_the_pieces_on_the_background() {
+        let theme = Theme::default();
+        let board = one_piece();
+        let image = board.to_image(101, &theme);
+        assert_eq!(image.dimensions(), (101, 101));
+        let pixel = |position: IVec2| {
+            let extent = board.scene(&theme).extent;
+            let p = (Board::physical_position(&position) + extent) * 101.0 / (2.0 * extent);
+            *image.get_pixel(p.x as u32, 101 - p.y as u32)
+        };
+        let opaque = |color| {
+            let [r, g, b] = Paint::from(color).rgb;
+            Rgba([r, g, b, 255])
+        };
+
+        // the corners lie outside the star
+        assert_eq!(*image.get_pixel(0, 0), opaque(theme.background()));
+        assert_eq!(
+            pixel(IVec2::ZERO),
+            opaque(theme.piece_color(Piece::Player1))
+        );
+        assert_eq!(pixel(ivec2(2, -1)), opaque(theme.piece_color(Piece::None)));
+    }
+
+    #[test]
+    fn png_decodes_to_the_image() {
+        let theme = Theme::default();
+        let board = one_piece();
+        let png = board.to_png(64, &theme).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+        let decoded = image::load_from_memory(&png).unwrap().to_rgba8();
+        assert_eq!(decoded, board.to_image(64, &theme));
+    }
+
+    #[test]
+    fn gif_has_a_frame_per_position() {
+        let mut board = Board::default();
+        board.reset(Mode::Two);
+        for _ in 0..2 {
+            let path = board.legal_paths().remove(0);
+            assert!(board.play_path(&path));
+        }
+        let gif = board.replay_gif(32, 100, &Theme::default()).unwrap();
+        let frames = GifDecoder::new(&gif[..])
+            .unwrap()
+            .into_frames()
+            .collect_frames()
+            .unwrap();
+        assert_eq!(frames.len(), 3);
+        assert!(frames
+            .iter()
+            .all(|frame| frame.buffer().dimensions() == (32, 32)
+                && frame.delay() == Delay::from_numer_denom_ms(100, 1)));
+    }
+}
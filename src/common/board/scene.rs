@@ -0,0 +1,232 @@
+//! The board as a list of flat shapes, for rendering it somewhere other than the window
+
+use nannou::{
+    color::encoding::{srgb, Linear},
+    prelude::*,
+};
+
+use super::{
+    super::{
+        player::{Piece, Turn},
+        theme::{linear, Color, Theme},
+    },
+    Board, HEX_SIZE,
+};
+
+/// A color in sRGB, with an opacity from 0 to 1
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Paint {
+    pub rgb: [u8; 3],
+    pub alpha: f32,
+}
+
+impl Paint {
+    pub fn opaque(rgb: [u8; 3]) -> Self {
+        Self { rgb, alpha: 1.0 }
+    }
+
+    /// The color as nannou draws it
+    pub fn srgba(&self) -> Srgba {
+        let [r, g, b] = self.rgb.map(|c| c as f32 / 255.0);
+        srgba(r, g, b, self.alpha)
+    }
+
+    /// Relative luminance, to pick something legible on top of this color
+    pub fn luminance(&self) -> f32 {
+        let [r, g, b] = self.rgb.map(|c| c as f32 / 255.0);
+        0.2126 * r + 0.7152 * g + 0.0722 * b
+    }
+}
+
+impl From<rgb::Rgb<Linear<srgb::Srgb>>> for Paint {
+    fn from(color: rgb::Rgb<Linear<srgb::Srgb>>) -> Self {
+        let encode = |c: f32| {
+            let c = c.clamp(0.0, 1.0);
+            let encoded = if c <= 0.003_130_8 {
+                12.92 * c
+            } else {
+                1.055 * c.powf(1.0 / 2.4) - 0.055
+            };
+            (encoded * 255.0).round() as u8
+        };
+        Self::opaque([encode(color.red), encode(color.green), encode(color.blue)])
+    }
+}
+
+pub enum Shape {
+    Polygon {
+        points: Vec<Point2>,
+        fill: Paint,
+        stroke: Option<(Paint, f32)>,
+    },
+    Circle {
+        center: Point2,
+        radius: f32,
+        fill: Paint,
+    },
+    Polyline {
+        points: Vec<Point2>,
+        width: f32,
+        color: Paint,
+    },
+}
+
+/// `n` corners around the center, alternating between the outer and inner radius when they differ
+fn star(center: Point2, n: usize, outer: f32, inner: f32, start: f32) -> Vec<Point2> {
+    (0..n)
+        .map(|i| {
+            let angle = start + 2.0 * f32::PI() * i as f32 / n as f32;
+            let radius = if i % 2 == 0 { outer } else { inner };
+            center + pt2(angle.cos(), angle.sin()) * radius
+        })
+        .collect()
+}
+
+/// The glyph telling the pieces of the given player apart without relying on their color. All of
+/// a player's pieces get the same glyph, however many kinds of piece they play.
+fn marker(owner: Turn, center: Point2, radius: f32, fill: Paint) -> Option<Shape> {
+    let r = radius * 0.55;
+    let quarter = f32::PI() / 2.0;
+    let points = match owner {
+        Turn::None => return None,
+        Turn::Player1 => {
+            return Some(Shape::Circle {
+                center,
+                radius: r * 0.6,
+                fill,
+            })
+        }
+        Turn::Player2 => star(center, 3, r, r, quarter),
+        Turn::Player3 => star(center, 4, r, r, quarter / 2.0),
+        Turn::Player4 => star(center, 4, r, r, 0.0),
+        Turn::Player5 => {
+            let w = r * 0.35;
+            [(w, r), (w, w), (r, w), (r, -w), (w, -w), (w, -r)]
+                .into_iter()
+                .chain([(-w, -r), (-w, -w), (-r, -w), (-r, w), (-w, w), (-w, r)])
+                .map(|(x, y)| center + pt2(x, y))
+                .collect()
+        }
+        Turn::Player6 => star(center, 10, r, r * 0.45, quarter),
+    };
+    Some(Shape::Polygon {
+        points,
+        fill,
+        stroke: None,
+    })
+}
+
+pub struct Scene {
+    /// Half the width of the square the scene fits in, centered on the origin
+    pub extent: f32,
+    pub background: Paint,
+    /// Shapes to draw, back to front
+    pub shapes: Vec<Shape>,
+}
+
+impl Board {
+    /// The color of the given kind of piece: the one its player picked for their seat, if any,
+    /// otherwise the theme's
+    pub fn piece_color(&self, theme: &Theme, piece: Piece) -> Color {
+        match self.seat(self.owner(piece)).and_then(|seat| seat.color) {
+            Some([r, g, b]) => linear(Srgb::new(r, g, b)),
+            None => theme.piece_color(piece),
+        }
+    }
+
+    /// The same shapes `draw` puts on screen, in board coordinates (pointing up)
+    pub fn scene(&self, theme: &Theme) -> Scene {
+        let mut shapes = vec![Shape::Polygon {
+            points: Self::hexagon().take(6).collect(),
+            fill: theme.board_color().into(),
+            stroke: None,
+        }];
+
+        for (corners, piece_kind) in Self::home_triangles() {
+            let (fill, stroke) = theme.triangle_colors(self.piece_color(theme, piece_kind));
+            shapes.push(Shape::Polygon {
+                points: corners.to_vec(),
+                fill: fill.into(),
+                stroke: self
+                    .turn
+                    .owns(piece_kind, self.mode)
+                    .then(|| (stroke.into(), Self::TRIANGLE_STROKE)),
+            });
+        }
+
+        for (pos, state) in self.backing.iter() {
+            let center = Self::physical_position(&pos);
+            let fill: Paint = self.piece_color(theme, state).into();
+            shapes.push(Shape::Circle {
+                center,
+                radius: Self::WIDTH,
+                fill,
+            });
+            if theme.markers {
+                let glyph = if fill.luminance() > 0.5 {
+                    [0; 3]
+                } else {
+                    [255; 3]
+                };
+                let owner = self.owner(state);
+                shapes.extend(marker(owner, center, Self::WIDTH, Paint::opaque(glyph)));
+            }
+        }
+
+        let (color, alpha) = theme.highlight();
+        let highlight = Paint {
+            alpha,
+            ..color.into()
+        };
+        let path = self
+            .path
+            .iter()
+            .map(Self::physical_position)
+            .collect::<Vec<_>>();
+        for &center in &path {
+            shapes.push(Shape::Circle {
+                center,
+                radius: Self::HIGHLIGHT_WIDTH,
+                fill: highlight,
+            });
+        }
+        if path.len() > 1 {
+            shapes.push(Shape::Polyline {
+                points: path,
+                width: 2.0 * Self::HIGHLIGHT_WIDTH,
+                color: highlight,
+            });
+        }
+
+        Scene {
+            // the tips of the star lie at a distance of sqrt(3) * HEX_SIZE from the center
+            extent: HEX_SIZE * 3.0f32.sqrt() + Self::WIDTH,
+            background: theme.background().into(),
+            shapes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Mode;
+    use super::*;
+
+    #[test]
+    fn markers_follow_the_owner_of_the_pieces() {
+        let mut board = Board::default();
+        board.reset(Mode::Two);
+        let theme = Theme {
+            markers: true,
+            ..Theme::default()
+        };
+        // Player1 plays three kinds of piece, all marked with a dot
+        let dots = board
+            .scene(&theme)
+            .shapes
+            .iter()
+            .filter(|shape| matches!(shape, Shape::Circle { radius, .. } if *radius < Board::WIDTH))
+            .count();
+        assert_eq!(dots, 30);
+    }
+}
@@ -0,0 +1,137 @@
+//! Standings of the players: who finished in which place, and how far along everyone else is
+
+use strum::IntoEnumIterator;
+
+use super::{
+    super::player::{Piece, Turn},
+    cells::{self, Cells},
+    Board,
+};
+
+/// Points for finishing, on top of the most a player can score without finishing
+const FINISH_POINTS: u32 = 100;
+/// Points for every place a finisher is ahead of the last one
+const PLACE_POINTS: u32 = 10;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Standing {
+    pub turn: Turn,
+    /// Rank from 1: finishers in the order they finished, then everyone else by score, then
+    /// whoever forfeited
+    pub place: usize,
+    pub finished: bool,
+    pub forfeited: bool,
+    pub pieces_in_target: u32,
+    pub pieces: u32,
+    pub distance_left: i32,
+    /// Finishers score at least [`FINISH_POINTS`], more the earlier they finished. Everyone else
+    /// scores up to 100 by how many pieces they brought into their targets and how much of the
+    /// way to their targets they covered, half each. Forfeiting scores nothing.
+    pub score: u32,
+}
+
+impl Board {
+    /// Number of the player's pieces sitting in their targets, and of the player's pieces overall
+    pub fn pieces_in_target(&self, turn: Turn) -> (u32, u32) {
+        Piece::iter()
+            .filter(|&kind| turn.owns(kind, self.mode))
+            .fold((0, 0), |(arrived, all), kind| {
+                let pieces = self.backing.bitboard(kind);
+                (
+                    arrived + (pieces & Cells::home_mask(kind.opposite())).count_ones(),
+                    all + pieces.count_ones(),
+                )
+            })
+    }
+
+    /// Sum of the distances from each of the player's pieces to their targets at the start of a
+    /// game, when every piece sits in its home
+    fn start_distance(&self, turn: Turn) -> i32 {
+        Piece::iter()
+            .filter(|&kind| turn.owns(kind, self.mode))
+            .flat_map(|kind| {
+                let tip = Self::target_tip(kind).unwrap();
+                cells::indices(Cells::home_mask(kind))
+                    .map(move |i| Self::hex_distance(Cells::position(i), tip))
+            })
+            .sum()
+    }
+
+    /// Every player of the game, best first
+    pub fn standings(&self) -> Vec<Standing> {
+        let players = self.mode.players() as u32;
+
+        let mut standings = self
+            .mode
+            .turns()
+            .map(|turn| {
+                let (pieces_in_target, pieces) = self.pieces_in_target(turn);
+                let distance_left = self.distance_to_target(turn);
+                let placing = self.placings.iter().position(|&placed| placed == turn);
+                let forfeited = self.forfeited.contains(&turn);
+                let score = match placing {
+                    Some(i) => FINISH_POINTS + PLACE_POINTS * (players - 1 - i as u32),
+                    None if forfeited => 0,
+                    None => {
+                        let full_distance = self.start_distance(turn).max(1);
+                        let covered = (full_distance - distance_left).clamp(0, full_distance);
+                        50 * pieces_in_target / pieces.max(1)
+                            + (50 * covered / full_distance) as u32
+                    }
+                };
+                Standing {
+                    turn,
+                    place: 0,
+                    finished: placing.is_some(),
+                    forfeited,
+                    pieces_in_target,
+                    pieces,
+                    distance_left,
+                    score,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        standings.sort_by_key(|standing| {
+            let placing = self
+                .placings
+                .iter()
+                .position(|&placed| placed == standing.turn);
+            (
+                standing.forfeited,
+                placing.unwrap_or(usize::MAX),
+                u32::MAX - standing.score,
+                standing.distance_left,
+            )
+        });
+        for (i, standing) in standings.iter_mut().enumerate() {
+            standing.place = i + 1;
+        }
+        standings
+    }
+
+    /// The standings as a small table of text, one player per line
+    pub fn standings_text(&self) -> String {
+        self.standings()
+            .iter()
+            .map(|standing| {
+                let status = if standing.forfeited {
+                    "forfeited".to_string()
+                } else if standing.finished {
+                    "finished".to_string()
+                } else {
+                    format!("{} left", standing.distance_left)
+                };
+                format!(
+                    "{}. {}: {} points, {}/{} in target, {status}",
+                    standing.place,
+                    self.player_name(standing.turn),
+                    standing.score,
+                    standing.pieces_in_target,
+                    standing.pieces
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
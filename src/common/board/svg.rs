@@ -0,0 +1,144 @@
+//! Renders the board to SVG, using the same shapes and colors as drawing it on screen
+
+use std::fmt::Write;
+
+use nannou::prelude::*;
+
+use super::{
+    super::theme::Theme,
+    scene::{Paint, Shape},
+    Board,
+};
+
+fn hex_color(paint: Paint) -> String {
+    let [r, g, b] = paint.rgb;
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+fn points(points: &[Point2]) -> String {
+    points
+        .iter()
+        .map(|p| format!("{:.4},{:.4}", p.x, p.y))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl Board {
+    /// An SVG image of the board, its pieces and the path currently being entered
+    pub fn to_svg(&self, size: u32, theme: &Theme) -> String {
+        let scene = self.scene(theme);
+        let extent = scene.extent;
+
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{size}" height="{size}" viewBox="{} {} {} {}">"#,
+            -extent,
+            -extent,
+            2.0 * extent,
+            2.0 * extent
+        );
+        let _ = writeln!(
+            svg,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+            -extent,
+            -extent,
+            2.0 * extent,
+            2.0 * extent,
+            hex_color(scene.background)
+        );
+        // board coordinates point up, while SVG coordinates point down
+        svg += "<g transform=\"scale(1,-1)\">\n";
+
+        for shape in &scene.shapes {
+            let _ = match shape {
+                Shape::Polygon {
+                    points: corners,
+                    fill,
+                    stroke,
+                } => {
+                    let stroke = stroke
+                        .map(|(color, width)| {
+                            format!(
+                                r#" stroke="{}" stroke-opacity="{}" stroke-width="{width}""#,
+                                hex_color(color),
+                                color.alpha
+                            )
+                        })
+                        .unwrap_or_default();
+                    writeln!(
+                        svg,
+                        r#"<polygon points="{}" fill="{}" fill-opacity="{}"{stroke}/>"#,
+                        points(corners),
+                        hex_color(*fill),
+                        fill.alpha
+                    )
+                }
+                Shape::Circle {
+                    center,
+                    radius,
+                    fill,
+                } => writeln!(
+                    svg,
+                    r#"<circle cx="{:.4}" cy="{:.4}" r="{radius:.4}" fill="{}" fill-opacity="{}"/>"#,
+                    center.x,
+                    center.y,
+                    hex_color(*fill),
+                    fill.alpha
+                ),
+                Shape::Polyline {
+                    points: line,
+                    width,
+                    color,
+                } => writeln!(
+                    svg,
+                    r#"<polyline points="{}" fill="none" stroke="{}" stroke-opacity="{}" stroke-width="{width:.4}"/>"#,
+                    points(line),
+                    hex_color(*color),
+                    color.alpha
+                ),
+            };
+        }
+
+        svg += "</g>\n</svg>\n";
+        svg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{cells::Cells, Mode, Piece};
+    use super::*;
+
+    #[test]
+    fn svg_draws_a_known_position() {
+        let mut board = Board::default();
+        board.reset(Mode::Two);
+        board.backing = Cells::empty();
+        board.backing.set(IVec2::ZERO, Piece::Player1);
+        board.path = vec![IVec2::ZERO, IVec2::X];
+        let mut theme = Theme::default();
+
+        let svg = board.to_svg(200, &theme);
+        assert!(
+            svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="200""#)
+        );
+        assert!(svg.ends_with("</g>\n</svg>\n"));
+        // the background, the hexagon and the six home triangles
+        assert!(svg.contains(r##"fill="#faebd7"/>"##));
+        assert_eq!(svg.matches("<polygon").count(), 7);
+        // every cell, and the two cells of the path
+        assert_eq!(svg.matches("<circle").count(), 123);
+        assert_eq!(svg.matches(r##"fill="#2f4f4f""##).count(), 120);
+        let piece = format!(
+            r##"<circle cx="0.0000" cy="0.0000" r="{:.4}" fill="#9932cc""##,
+            Board::WIDTH
+        );
+        assert!(svg.contains(&piece));
+        assert_eq!(svg.matches("<polyline").count(), 1);
+
+        // the piece gets a marker
+        theme.markers = true;
+        assert_eq!(board.to_svg(200, &theme).matches("<circle").count(), 124);
+    }
+}
@@ -0,0 +1,55 @@
+//! Random keys for Zobrist hashing. A position's hash is the xor of the keys of every piece on its
+//! cell, plus the key of the player to move, so it can be updated piece by piece as moves are made.
+
+use once_cell::sync::Lazy;
+use strum::IntoEnumIterator;
+
+use super::{cells::CELL_COUNT, Piece, Turn};
+
+static KEYS: Lazy<Keys> = Lazy::new(Keys::new);
+
+struct Keys {
+    pieces: Vec<[u64; 7]>,
+    turns: [u64; 7],
+}
+
+impl Keys {
+    /// The keys are generated from a fixed seed so that hashes stay the same between runs, which
+    /// lets them be stored (e.g. in an opening book)
+    fn new() -> Self {
+        // splitmix64
+        let mut state = 0x6363_6865_636b_6572u64;
+        let mut next = || {
+            state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^ (z >> 31)
+        };
+
+        let pieces = (0..CELL_COUNT)
+            .map(|_| {
+                let mut keys = [0; 7];
+                // empty cells do not contribute, so that an empty board hashes to 0
+                for piece in Piece::iter().skip(1) {
+                    keys[piece as usize] = next();
+                }
+                keys
+            })
+            .collect();
+        let mut turns = [0; 7];
+        for turn in Turn::iter().skip(1) {
+            turns[turn as usize] = next();
+        }
+
+        Self { pieces, turns }
+    }
+}
+
+pub fn piece_key(index: usize, piece: Piece) -> u64 {
+    KEYS.pieces[index][piece as usize]
+}
+
+pub fn turn_key(turn: Turn) -> u64 {
+    KEYS.turns[turn as usize]
+}
@@ -0,0 +1,212 @@
+//! Opening books: the moves played from the positions early on in known games, for bots to pick
+//! from instead of searching
+
+use std::collections::HashMap;
+
+use nannou::{
+    prelude::*,
+    rand::{seq::SliceRandom, Rng},
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    board::{puzzle::parse_path, Board, Move},
+    player::{Mode, Turn},
+};
+
+/// How many moves into a game a book is consulted, unless it says otherwise
+pub const DEFAULT_MOVES: usize = 12;
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Book {
+    /// How many moves into a game, counting every player's, the book is consulted
+    pub moves: usize,
+    /// For each mode, the moves known from positions by the positions' hashes. Moves are written
+    /// like `c5-e7-g9` and weighted by how often and how well they were played.
+    pub positions: HashMap<Mode, HashMap<u64, Vec<(String, u32)>>>,
+}
+
+impl Default for Book {
+    fn default() -> Self {
+        Self {
+            moves: DEFAULT_MOVES,
+            positions: HashMap::new(),
+        }
+    }
+}
+
+impl Book {
+    /// Adds the opening of a game played from the start. Every move counts once, and once more if
+    /// its player won the game.
+    pub fn add_game(
+        &mut self,
+        mode: Mode,
+        history: &[Move],
+        winner: Option<Turn>,
+    ) -> Result<(), String> {
+        let mut board = Board::default();
+        board.reset(mode);
+        let positions = self.positions.entry(mode).or_default();
+        for played in history.iter().take(self.moves) {
+            // players who had nothing to play passed
+            for _ in 0..mode.players() {
+                if board.turn == played.turn {
+                    break;
+                }
+                board.skip_turn();
+            }
+
+            let hash = board.hash();
+            let name = Board::path_name(&played.path);
+            if !board.play_path(&played.path) {
+                return Err(format!("{name} is not legal"));
+            }
+            let weight = 1 + (winner == Some(played.turn)) as u32;
+            let known = positions.entry(hash).or_default();
+            match known.iter_mut().find(|(path, _)| *path == name) {
+                Some((_, total)) => *total += weight,
+                None => known.push((name, weight)),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let data =
+            std::fs::read_to_string(path).map_err(|e| format!("could not read {path}: {e}"))?;
+        ron::from_str(&data).map_err(|e| format!("could not load {path}: {e}"))
+    }
+
+    /// Number of positions the book knows moves from
+    pub fn len(&self) -> usize {
+        self.positions.values().map(HashMap::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// A move the book knows from the position, picked at random by weight, as long as the game
+    /// is young enough
+    pub fn choose(&self, board: &Board, rng: &mut impl Rng) -> Option<Vec<IVec2>> {
+        if board.history.len() >= self.moves {
+            return None;
+        }
+        // hashes can collide, so only legal moves are considered
+        let known = self
+            .positions
+            .get(&board.mode)?
+            .get(&board.hash())?
+            .iter()
+            .filter_map(|(name, weight)| {
+                let path = parse_path(name)?;
+                board.clone().play_path(&path).then_some((path, *weight))
+            })
+            .collect::<Vec<_>>();
+        known
+            .choose_weighted(rng, |(_, weight)| *weight)
+            .ok()
+            .map(|(path, _)| path.clone())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Book {
+    const FILE: &'static str = "book.ron";
+
+    /// The book the computer seats of the window play from, if one was set
+    pub fn load_default() -> Option<Self> {
+        super::read_config(Self::FILE).filter(|book: &Self| !book.is_empty())
+    }
+
+    /// Makes this the book the computer seats of the window play from. An empty book takes
+    /// the book away from them.
+    pub fn save_as_default(&self) -> std::io::Result<()> {
+        super::write_config(Self::FILE, self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nannou::rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    /// The first legal moves of a game in the mode, one per player in turn
+    fn opening(mode: Mode, moves: usize) -> Vec<Move> {
+        let mut board = Board::default();
+        board.reset(mode);
+        for _ in 0..moves {
+            let path = board.legal_paths().remove(0);
+            board.play_path(&path);
+        }
+        board.history
+    }
+
+    fn start(mode: Mode) -> Board {
+        let mut board = Board::default();
+        board.reset(mode);
+        board
+    }
+
+    #[test]
+    fn moves_of_the_winner_count_twice() {
+        let history = opening(Mode::Two, 3);
+        let mut book = Book {
+            moves: 2,
+            ..Book::default()
+        };
+        book.add_game(Mode::Two, &history, Some(Turn::Player1))
+            .unwrap();
+        book.add_game(Mode::Two, &history, None).unwrap();
+
+        // only the first two moves went in
+        assert_eq!(book.len(), 2);
+        let mut board = start(Mode::Two);
+        for (played, weight) in history.iter().zip([3, 2]) {
+            assert_eq!(
+                book.positions[&Mode::Two][&board.hash()],
+                vec![(Board::path_name(&played.path), weight)]
+            );
+            board.play_path(&played.path);
+        }
+    }
+
+    #[test]
+    fn games_with_illegal_moves_are_refused() {
+        let mut history = opening(Mode::Two, 1);
+        history[0].path.reverse();
+        assert!(Book::default().add_game(Mode::Two, &history, None).is_err());
+    }
+
+    #[test]
+    fn only_legal_moves_are_chosen_while_the_game_is_young() {
+        let history = opening(Mode::Two, 2);
+        let mut book = Book {
+            moves: 2,
+            ..Book::default()
+        };
+        book.add_game(Mode::Two, &history, None).unwrap();
+        let mut board = start(Mode::Two);
+        // a colliding hash could bring moves that cannot be played, even with a heavy weight
+        let illegal = history[0].path.iter().rev().copied().collect::<Vec<_>>();
+        book.positions
+            .get_mut(&Mode::Two)
+            .unwrap()
+            .get_mut(&board.hash())
+            .unwrap()
+            .push((Board::path_name(&illegal), 100));
+
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..20 {
+            assert_eq!(book.choose(&board, &mut rng), Some(history[0].path.clone()));
+        }
+
+        // the book knows the next position, but not once it is past the book's moves
+        board.play_path(&history[0].path);
+        assert_eq!(book.choose(&board, &mut rng), Some(history[1].path.clone()));
+        book.moves = 1;
+        assert_eq!(book.choose(&board, &mut rng), None);
+    }
+}
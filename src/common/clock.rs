@@ -0,0 +1,103 @@
+use std::{collections::HashMap, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumIter};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use super::player::{Mode, Turn};
+
+/// What happens to a player who runs out of time
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug, EnumIter, Display, Serialize, Deserialize)]
+pub enum FlagFall {
+    /// The player forfeits the game
+    #[default]
+    Lose,
+    /// The player's turn is skipped
+    Pass,
+    /// A random legal move is played for the player
+    RandomMove,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct TimeControl {
+    /// Time each player starts out with. When it runs out, the flag falls.
+    pub initial: Duration,
+    /// Time added to a player's clock after each of their moves
+    pub increment: Duration,
+    /// Longest a single move may take, regardless of the time left on the clock
+    pub per_move: Option<Duration>,
+    pub flag_fall: FlagFall,
+}
+
+impl Default for TimeControl {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_secs(5 * 60),
+            increment: Duration::ZERO,
+            per_move: None,
+            flag_fall: FlagFall::default(),
+        }
+    }
+}
+
+/// The clocks of every player in a game. Without a time control, the clocks never run.
+#[derive(Clone, PartialEq, Eq, Default, Debug, Serialize, Deserialize)]
+pub struct Clocks {
+    pub control: Option<TimeControl>,
+    remaining: HashMap<Turn, Duration>,
+    this_move: Duration,
+}
+
+impl Clocks {
+    /// Sets every player's clock back to the initial time
+    pub fn start(&mut self, mode: Mode) {
+        self.this_move = Duration::ZERO;
+        self.remaining = match self.control {
+            Some(control) => mode.turns().map(|turn| (turn, control.initial)).collect(),
+            None => HashMap::new(),
+        };
+    }
+
+    pub fn remaining(&self, turn: Turn) -> Option<Duration> {
+        self.remaining.get(&turn).copied()
+    }
+
+    /// Time the current move has taken so far
+    pub fn this_move(&self) -> Duration {
+        self.this_move
+    }
+
+    /// Runs the player's clock and returns true if their flag fell
+    pub fn run(&mut self, turn: Turn, elapsed: Duration) -> bool {
+        let (Some(control), Some(remaining)) = (self.control, self.remaining.get_mut(&turn)) else {
+            return false;
+        };
+        *remaining = remaining.saturating_sub(elapsed);
+        self.this_move += elapsed;
+        remaining.is_zero()
+            || control
+                .per_move
+                .is_some_and(|limit| self.this_move >= limit)
+    }
+
+    /// Stops the player's clock after they moved, giving them their increment
+    pub fn moved(&mut self, turn: Turn) {
+        if let (Some(control), Some(remaining)) = (self.control, self.remaining.get_mut(&turn)) {
+            *remaining += control.increment;
+        }
+        self.this_move = Duration::ZERO;
+    }
+
+    /// Starts timing a new move without crediting anyone an increment
+    pub fn passed(&mut self) {
+        self.this_move = Duration::ZERO;
+    }
+}
+
+/// Formats a clock reading as minutes and seconds
+pub fn format_clock(time: Duration) -> String {
+    let seconds = time.as_secs();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
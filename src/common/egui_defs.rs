@@ -1,21 +1,137 @@
 use std::{
     fs::{self, File},
     io::Write,
+    sync::{
+        mpsc::{self, Receiver, TryRecvError},
+        Arc,
+    },
+    time::{Duration, Instant},
 };
 
 use super::{
-    board::Board,
-    player::{Mode, Turn},
+    ai,
+    analysis::Analysis,
+    board::{
+        editing::Brush,
+        endgame::RaceBudget,
+        puzzle::{self, Progress, Puzzle},
+        Board, Move, Outcome,
+    },
+    book::Book,
+    clock::{self, FlagFall, TimeControl},
+    player::{Controller, Mode, Piece, Seat, Turn},
+    rules::{HomeLimit, LimitResult, Rules},
+    stats::Stats,
+    theme::{Palette, Theme},
     Model,
 };
 use nannou::prelude::*;
-use nannou_egui::egui;
+use nannou_egui::egui::{
+    self,
+    plot::{Legend, Line, Plot, Points, Value, Values},
+};
 use rfd::FileDialog;
 use strum::IntoEnumIterator;
 
 #[derive(Default)]
 pub struct EguiData {
     mode: Mode,
+    rules: Rules,
+    time_control: Option<TimeControl>,
+    seats: [Seat; 6],
+    pub seat_error: Option<String>,
+    /// The opening book computer seats play from
+    book: Option<Arc<Book>>,
+    /// The results of the finished game were closed
+    results_dismissed: bool,
+    stats: Stats,
+    /// The finished game is already in the statistics
+    recorded: bool,
+    /// When the game on the board was started or loaded
+    game_started: Option<Instant>,
+    puzzles: Vec<Puzzle>,
+    chosen_puzzle: usize,
+    /// The puzzle being played on the board
+    puzzle: Option<Puzzle>,
+    puzzle_error: Option<String>,
+    show_solution: bool,
+    /// The solved race, with the hash of the position it was solved for
+    race: Option<(u64, String)>,
+    /// The analysed game and its analysis
+    analysis: Option<(Board, Analysis)>,
+    /// Where the analysis being worked out in the background arrives
+    analysing: Option<Receiver<(Board, Analysis)>>,
+    /// The positions of the game on the board, for previewing earlier moves
+    replay: Replay,
+    /// Where the result of the GIF being encoded in the background arrives
+    exporting_gif: Option<Receiver<Result<(), String>>>,
+    export_error: Option<String>,
+}
+
+impl EguiData {
+    pub fn new() -> Self {
+        Self {
+            stats: Stats::load(),
+            game_started: Some(Instant::now()),
+            puzzles: puzzle::bundled(),
+            book: Book::load_default().map(Arc::new),
+            ..Default::default()
+        }
+    }
+}
+
+/// Every position of a game, worked out again only once its moves change
+#[derive(Default)]
+struct Replay {
+    history: Vec<Move>,
+    positions: Vec<Board>,
+}
+
+impl Replay {
+    /// The board after each of the moves of the game, as [`Board::replay`] gives them
+    fn of(&mut self, board: &Board) -> &[Board] {
+        if self.positions.is_empty() || self.history != board.history {
+            self.history = board.history.clone();
+            self.positions = board.replay();
+        }
+        &self.positions
+    }
+}
+
+/// Built-in bots offered for computer seats, by name and description
+const COMPUTER_LEVELS: [(&str, &str); 5] = [
+    ("random", "Random"),
+    ("greedy", "Greedy"),
+    ("search:1", "Search, depth 1"),
+    ("search:2", "Search, depth 2"),
+    ("search:3", "Search, depth 3"),
+];
+
+/// Replaces the bots with ones playing the computer, engine and network seats of the board,
+/// returning what went wrong starting any of them. Computer seats play from the book while it
+/// knows the position; engines and network seats always choose their own moves.
+fn start_bots(board: &Board, book: &Option<Arc<Book>>, bots: &mut ai::Bots) -> Option<String> {
+    bots.clear();
+    let mut error = None;
+    for (i, turn) in board.mode.turns().enumerate() {
+        let Some(seat) = board.seat(turn) else {
+            continue;
+        };
+        match ai::bot_for_seat(seat, i as u64) {
+            Ok(Some(bot)) => {
+                let bot: Box<dyn ai::Bot> = match (book, &seat.controller) {
+                    (Some(book), Controller::Computer(_)) => {
+                        Box::new(ai::BookBot::new(book.clone(), i as u64, bot))
+                    }
+                    _ => bot,
+                };
+                bots.insert(turn, bot);
+            }
+            Err(e) => error = Some(format!("{}: {e}", board.player_name(turn))),
+            Ok(None) => (),
+        }
+    }
+    error
 }
 
 fn load_game() -> Option<Board> {
@@ -31,10 +147,73 @@ fn save_game(board: &Board) {
         .save_file()
     {
         if let Ok(mut f) = File::create(file_handle) {
-            f.write_all(ron::to_string(board).unwrap().as_bytes());
+            let _ = f.write_all(ron::to_string(board).unwrap().as_bytes());
+        }
+    }
+}
+
+fn load_puzzles() -> Option<Result<Vec<Puzzle>, String>> {
+    let path = FileDialog::new()
+        .add_filter("Puzzles", &["ron"])
+        .pick_file()?;
+    Some(
+        fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|str| ron::from_str(&str).map_err(|e| e.to_string())),
+    )
+}
+
+fn load_book() -> Option<Result<Book, String>> {
+    let path = FileDialog::new()
+        .add_filter("Opening book", &["ron"])
+        .pick_file()?;
+    Some(Book::load(&path.to_string_lossy()))
+}
+
+fn export_csv(file_name: &str, csv: String) {
+    if let Some(file_handle) = FileDialog::new().set_file_name(file_name).save_file() {
+        let _ = fs::write(file_handle, csv);
+    }
+}
+
+fn export_svg(board: &Board, theme: &Theme) {
+    if let Some(file_handle) = FileDialog::new()
+        .set_file_name("ccheckers_board.svg")
+        .save_file()
+    {
+        let _ = fs::write(file_handle, board.to_svg(800, theme));
+    }
+}
+
+fn export_png(board: &Board, theme: &Theme) {
+    if let Some(file_handle) = FileDialog::new()
+        .set_file_name("ccheckers_board.png")
+        .save_file()
+    {
+        if let Ok(png) = board.to_png(800, theme) {
+            let _ = fs::write(file_handle, png);
         }
     }
 }
+
+/// Encodes the GIF in the background, since every position of the game has to be rasterized.
+/// Returns where the result arrives, or None if no file was picked.
+fn export_gif(board: &Board, theme: &Theme) -> Option<Receiver<Result<(), String>>> {
+    let file_handle = FileDialog::new()
+        .set_file_name("ccheckers_game.gif")
+        .save_file()?;
+    let (board, theme) = (board.clone(), *theme);
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let result = board
+            .replay_gif(400, 700, &theme)
+            .map_err(|e| e.to_string())
+            .and_then(|gif| fs::write(file_handle, gif).map_err(|e| e.to_string()));
+        let _ = sender.send(result);
+    });
+    Some(receiver)
+}
+
 pub fn define_ui(model: &mut Model, update: &Update) {
     let Model { ref mut egui, .. } = *model;
     egui.set_elapsed_time(update.since_start);
@@ -54,7 +233,20 @@ pub fn define_ui(model: &mut Model, update: &Update) {
             });
         ui.horizontal(|ui| {
             if ui.button("Reset field").clicked() {
+                let players = model.egui_data.mode.players();
+                model.board.rules = model.egui_data.rules;
+                model.board.clocks.control = model.egui_data.time_control;
+                model.board.seats = model.egui_data.seats[..players].to_vec();
                 model.board.reset(model.egui_data.mode);
+                model.preview = None;
+                model.egui_data.results_dismissed = false;
+                model.egui_data.recorded = false;
+                model.egui_data.game_started = Some(Instant::now());
+                model.egui_data.analysis = None;
+                model.egui_data.puzzle = None;
+                model.editing = None;
+                model.egui_data.seat_error =
+                    start_bots(&model.board, &model.egui_data.book, &mut model.bots);
             }
 
             if ui.button("Save game").clicked() {
@@ -64,12 +256,722 @@ pub fn define_ui(model: &mut Model, update: &Update) {
             if ui.button("Load game").clicked() {
                 if let Some(board) = load_game() {
                     model.board = board;
+                    model.preview = None;
+                    model.egui_data.results_dismissed = false;
+                    model.egui_data.recorded = model.board.outcome().is_some();
+                    model.egui_data.game_started = Some(Instant::now());
+                    model.egui_data.analysis = None;
+                    model.egui_data.puzzle = None;
+                    model.editing = None;
+                    for (edited, seat) in model.egui_data.seats.iter_mut().zip(&model.board.seats) {
+                        *edited = seat.clone();
+                    }
+                    model.egui_data.seat_error =
+                        start_bots(&model.board, &model.egui_data.book, &mut model.bots);
+                }
+            }
+
+            if ui.button("Export SVG").clicked() {
+                export_svg(&model.board, &model.theme);
+            }
+
+            if ui.button("Export PNG").clicked() {
+                export_png(&model.board, &model.theme);
+            }
+
+            if let Some(receiver) = &model.egui_data.exporting_gif {
+                match receiver.try_recv() {
+                    Ok(result) => {
+                        model.egui_data.export_error = result.err();
+                        model.egui_data.exporting_gif = None;
+                    }
+                    Err(TryRecvError::Empty) => {
+                        ui.label("Exporting GIF...");
+                    }
+                    Err(TryRecvError::Disconnected) => model.egui_data.exporting_gif = None,
+                }
+            } else if ui.button("Export GIF").clicked() {
+                model.egui_data.exporting_gif = export_gif(&model.board, &model.theme);
+            }
+
+            if ui.button("Hint").clicked() {
+                if let Some(path) = ai::hint(&model.board) {
+                    model.board.set_path(path);
+                }
+            }
+        });
+        if let Some(e) = &model.egui_data.export_error {
+            ui.label(format!("Exporting the GIF failed: {e}"));
+        }
+
+        ui.collapsing("Tournament rules (applied on reset)", |ui| {
+            let rules = &mut model.egui_data.rules;
+            ui.checkbox(
+                &mut rules.anti_spoiling,
+                "Blocked target cells count as filled",
+            );
+            ui.checkbox(
+                &mut rules.play_on,
+                "Play on after the first player finishes, to decide every place",
+            );
+            let mut home_limit = rules.home_limit.is_some();
+            ui.checkbox(&mut home_limit, "Limit pieces left at home");
+            match (home_limit, &mut rules.home_limit) {
+                (true, Some(limit)) => {
+                    ui.horizontal(|ui| {
+                        ui.label("At most");
+                        ui.add(egui::DragValue::new(&mut limit.max_pieces).clamp_range(0..=30));
+                        ui.label("pieces after");
+                        ui.add(egui::DragValue::new(&mut limit.after_moves).clamp_range(1..=500));
+                        ui.label("moves");
+                    });
+                }
+                (true, limit @ None) => *limit = Some(HomeLimit::default()),
+                (false, limit) => *limit = None,
+            }
+
+            optional_count(
+                ui,
+                "End on repetition, times:",
+                &mut rules.repetition,
+                3,
+                2..=10,
+            );
+            optional_count(ui, "Move limit:", &mut rules.move_limit, 400, 10..=5000);
+            egui::ComboBox::from_label("Games cut short are")
+                .selected_text(match rules.limit_result {
+                    LimitResult::Draw => "drawn",
+                    LimitResult::Progress => "won on progress",
+                })
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut rules.limit_result, LimitResult::Draw, "drawn");
+                    ui.selectable_value(
+                        &mut rules.limit_result,
+                        LimitResult::Progress,
+                        "won on progress",
+                    );
+                });
+        });
+
+        ui.collapsing("Clock (applied on reset)", |ui| {
+            let mut enabled = model.egui_data.time_control.is_some();
+            ui.checkbox(&mut enabled, "Play with a clock");
+            match (enabled, &mut model.egui_data.time_control) {
+                (true, Some(control)) => time_control_ui(ui, control),
+                (true, control @ None) => *control = Some(TimeControl::default()),
+                (false, control) => *control = None,
+            }
+        });
+
+        ui.collapsing("Appearance", |ui| {
+            let before = model.theme;
+            let theme = &mut model.theme;
+            egui::ComboBox::from_label("Colors")
+                .selected_text(format!("{}", theme.palette))
+                .show_ui(ui, |ui| {
+                    for palette in Palette::iter() {
+                        ui.selectable_value(&mut theme.palette, palette, format!("{palette}"));
+                    }
+                });
+            ui.checkbox(&mut theme.dark, "Dark mode");
+            ui.checkbox(&mut theme.markers, "Mark pieces with a shape per player");
+            if *theme != before {
+                let _ = theme.save();
+            }
+            ui.checkbox(
+                &mut model.view.follow_turn,
+                "Turn the board towards the player to move",
+            );
+            ui.checkbox(
+                &mut model.view.labels,
+                "Show cell names, and describe the cell under the cursor",
+            );
+            ui.horizontal(|ui| {
+                ui.label("Scroll to zoom, drag with the right mouse button to pan");
+                if ui.button("Reset view").clicked() {
+                    model.view.reset();
+                }
+            });
+        });
+
+        ui.collapsing("Seats (applied on reset)", |ui| {
+            let EguiData { mode, seats, .. } = &mut model.egui_data;
+            for (i, (turn, seat)) in mode.turns().zip(seats.iter_mut()).enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{turn}"));
+                    ui.add(egui::TextEdit::singleline(&mut seat.avatar).desired_width(24.0));
+                    ui.add(
+                        egui::TextEdit::singleline(&mut seat.name)
+                            .hint_text("Name")
+                            .desired_width(100.0),
+                    );
+                    let mut own_color = seat.color.is_some();
+                    ui.checkbox(&mut own_color, "Color");
+                    match (own_color, &mut seat.color) {
+                        (true, Some(color)) => {
+                            ui.color_edit_button_srgb(color);
+                        }
+                        (true, color @ None) => *color = Some([128; 3]),
+                        (false, color) => *color = None,
+                    }
+                    controller_ui(ui, i, &mut seat.controller);
+                });
+            }
+            ui.horizontal(|ui| {
+                let data = &mut model.egui_data;
+                match &data.book {
+                    Some(book) => ui.label(format!("Opening book of {} positions", book.len())),
+                    None => ui.label("No opening book"),
+                };
+                if ui.button("Load opening book").clicked() {
+                    match load_book() {
+                        Some(Ok(book)) => {
+                            let _ = book.save_as_default();
+                            data.book = Some(Arc::new(book));
+                        }
+                        Some(Err(e)) => data.seat_error = Some(e),
+                        None => (),
+                    }
+                }
+                if data.book.is_some() && ui.button("Stop using the book").clicked() {
+                    let _ = Book::default().save_as_default();
+                    data.book = None;
+                }
+            });
+            if let Some(e) = &model.egui_data.seat_error {
+                ui.label(e);
+            }
+        });
+
+        ui.collapsing("Moves", |ui| {
+            let shown = model.preview.as_ref().map(|(moves, _)| *moves);
+            let mut clicked = None;
+            egui::ScrollArea::vertical()
+                .max_height(200.0)
+                .show(ui, |ui| {
+                    for (i, played) in model.board.history.iter().enumerate() {
+                        let text = format!(
+                            "{}. {}: {}",
+                            i + 1,
+                            model.board.player_name(played.turn),
+                            Board::path_name(&played.path)
+                        );
+                        if ui.selectable_label(shown == Some(i + 1), text).clicked() {
+                            clicked = Some(i + 1);
+                        }
+                    }
+                });
+            match clicked {
+                Some(moves) if shown == Some(moves) => model.preview = None,
+                Some(moves) => {
+                    model.preview = model
+                        .egui_data
+                        .replay
+                        .of(&model.board)
+                        .get(moves)
+                        .map(|board| (moves, board.clone()))
                 }
+                None => (),
+            }
+            if model.preview.is_some() && ui.button("Back to the game").clicked() {
+                model.preview = None;
             }
         });
 
-        if model.board.turn != Turn::None {
-            ui.label(format!("Currently {}'s turn", model.board.turn));
+        ui.collapsing("Standings", |ui| {
+            standings_grid(ui, "standings", &model.board);
+            let hash = model.board.hash();
+            if model.board.is_race() && ui.button("Solve the race").clicked() {
+                let text = match model.board.race(RaceBudget::INTERACTIVE) {
+                    Some(race) => race.summary(&model.board),
+                    None => "The race is too long to solve".to_string(),
+                };
+                model.egui_data.race = Some((hash, text));
+            }
+            if let Some((_, text)) = model
+                .egui_data
+                .race
+                .as_ref()
+                .filter(|(solved, _)| *solved == hash)
+            {
+                ui.label(text);
+            }
+        });
+
+        ui.collapsing("Puzzles", |ui| {
+            let data = &mut model.egui_data;
+            let chosen = data.puzzles.get(data.chosen_puzzle);
+            egui::ComboBox::from_label("Puzzle")
+                .selected_text(chosen.map_or(String::new(), |puzzle| puzzle.name.clone()))
+                .show_ui(ui, |ui| {
+                    for (i, puzzle) in data.puzzles.iter().enumerate() {
+                        let text = format!("{} (finish in {})", puzzle.name, puzzle.moves);
+                        ui.selectable_value(&mut data.chosen_puzzle, i, text);
+                    }
+                });
+            let (mut start, mut leave) = (None, false);
+            ui.horizontal(|ui| {
+                if ui.button("Start puzzle").clicked() {
+                    start = data.puzzles.get(data.chosen_puzzle).cloned();
+                }
+                if ui.button("Load puzzles").clicked() {
+                    match load_puzzles() {
+                        Some(Ok(puzzles)) => {
+                            data.chosen_puzzle = data.puzzles.len();
+                            data.puzzles.extend(puzzles);
+                        }
+                        Some(Err(e)) => data.puzzle_error = Some(e),
+                        None => (),
+                    }
+                }
+            });
+            if let Some(e) = &data.puzzle_error {
+                ui.label(e);
+            }
+
+            if let Some(puzzle) = &data.puzzle {
+                ui.label(match puzzle.progress(&model.board) {
+                    Progress::Solving { moves_left } => format!(
+                        "{}: finish in {moves_left} more move{}",
+                        puzzle.name,
+                        if moves_left == 1 { "" } else { "s" }
+                    ),
+                    Progress::Solved => format!("{}: solved!", puzzle.name),
+                    Progress::Failed => format!("{}: not solved, try again", puzzle.name),
+                });
+                ui.checkbox(&mut data.show_solution, "Show the solution");
+                if data.show_solution {
+                    ui.label(if puzzle.solution.is_empty() {
+                        "This puzzle comes without a solution".to_string()
+                    } else {
+                        puzzle.solution.join(", ")
+                    });
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Retry").clicked() {
+                        start = Some(puzzle.clone());
+                    }
+                    leave = ui.button("Leave puzzle").clicked();
+                });
+            }
+            if leave {
+                data.puzzle = None;
+            }
+            if let Some(puzzle) = start {
+                match puzzle.board() {
+                    Ok(board) => {
+                        model.board = board;
+                        model.preview = None;
+                        model.bots.clear();
+                        model.editing = None;
+                        data.puzzle = Some(puzzle);
+                        data.puzzle_error = None;
+                        data.show_solution = false;
+                    }
+                    Err(e) => data.puzzle_error = Some(format!("{}: {e}", puzzle.name)),
+                }
+            }
+        });
+
+        ui.collapsing("Position editor", |ui| {
+            let mut editing = model.editing.is_some();
+            ui.checkbox(&mut editing, "Edit the position by clicking cells");
+            match (editing, model.editing) {
+                (true, None) => {
+                    model.editing = Some(Brush::Cycle);
+                    model.preview = None;
+                    model.egui_data.puzzle = None;
+                    model.board.set_path(Vec::new());
+                }
+                (false, Some(_)) => model.editing = None,
+                _ => (),
+            }
+            let brush = match &mut model.editing {
+                Some(brush) => brush,
+                None => return,
+            };
+
+            ui.horizontal_wrapped(|ui| {
+                ui.selectable_value(brush, Brush::Cycle, "Cycle");
+                for piece in Piece::iter() {
+                    let text = if piece.is_none() {
+                        "Empty".to_string()
+                    } else {
+                        piece.to_string()
+                    };
+                    ui.selectable_value(brush, Brush::Paint(piece), text);
+                }
+            });
+
+            let board = &mut model.board;
+            egui::ComboBox::from_label("Players")
+                .selected_text(format!("{}", board.mode))
+                .show_ui(ui, |ui| {
+                    for mode in Mode::iter() {
+                        ui.selectable_value(&mut board.mode, mode, format!("{mode}"));
+                    }
+                });
+            if !board.mode.turns().any(|turn| turn == board.turn) {
+                board.turn = Turn::Player1;
+            }
+            let names = board
+                .mode
+                .turns()
+                .map(|turn| (turn, board.player_name(turn)))
+                .collect::<Vec<_>>();
+            egui::ComboBox::from_label("To move")
+                .selected_text(board.player_name(board.turn))
+                .show_ui(ui, |ui| {
+                    for (turn, name) in names {
+                        ui.selectable_value(&mut board.turn, turn, name);
+                    }
+                });
+            ui.horizontal(|ui| {
+                if ui.button("Clear").clicked() {
+                    board.clear();
+                }
+                if ui.button("Starting position").clicked() {
+                    board.reset(board.mode);
+                }
+            });
+
+            let problems = board.problems();
+            for problem in &problems {
+                ui.label(problem);
+            }
+            if ui
+                .add_enabled(problems.is_empty(), egui::Button::new("Start playing"))
+                .clicked()
+            {
+                board.start_from_here();
+                model.editing = None;
+                model.egui_data.results_dismissed = false;
+                model.egui_data.recorded = false;
+                model.egui_data.game_started = Some(Instant::now());
+                model.egui_data.seat_error =
+                    start_bots(&model.board, &model.egui_data.book, &mut model.bots);
+            }
+        });
+
+        ui.collapsing("Analysis", |ui| {
+            let data = &mut model.egui_data;
+            if let Some(receiver) = &data.analysing {
+                match receiver.try_recv() {
+                    Ok(analysis) => {
+                        data.analysis = Some(analysis);
+                        data.analysing = None;
+                    }
+                    Err(TryRecvError::Empty) => {
+                        ui.label("Analysing the game...");
+                    }
+                    Err(TryRecvError::Disconnected) => data.analysing = None,
+                }
+            } else if ui.button("Analyse the game").clicked() {
+                let board = model.board.clone();
+                let (sender, receiver) = mpsc::channel();
+                std::thread::spawn(move || {
+                    let analysis = Analysis::of(&board);
+                    let _ = sender.send((board, analysis));
+                });
+                data.analysing = Some(receiver);
+            }
+
+            let (board, analysis) = match &data.analysis {
+                Some(analysed) => analysed,
+                None => return,
+            };
+            ui.label("Moves each player still needs to finish, estimated after every move");
+            let mut plot = Plot::new("analysis")
+                .height(160.0)
+                .legend(Legend::default());
+            for (i, &turn) in analysis.turns.iter().enumerate() {
+                let values = analysis
+                    .estimates
+                    .iter()
+                    .enumerate()
+                    .map(|(moves, estimates)| Value::new(moves as f64, estimates[i] as f64));
+                plot = plot.line(
+                    Line::new(Values::from_values_iter(values)).name(board.player_name(turn)),
+                );
+            }
+            let blunders = analysis.blunders.iter().map(|blunder| {
+                let player = analysis.turns.iter().position(|&turn| turn == blunder.turn);
+                let estimate = analysis.estimates[blunder.index + 1][player.unwrap()];
+                Value::new((blunder.index + 1) as f64, estimate as f64)
+            });
+            plot = plot.points(
+                Points::new(Values::from_values_iter(blunders))
+                    .radius(4.0)
+                    .name("Blunders"),
+            );
+            ui.add(plot);
+
+            if analysis.blunders.is_empty() {
+                ui.label("No blunders found");
+            }
+            for blunder in &analysis.blunders {
+                if ui
+                    .selectable_label(false, analysis.describe(blunder, board))
+                    .clicked()
+                {
+                    let moves = blunder.index + 1;
+                    model.preview = board
+                        .replay()
+                        .into_iter()
+                        .nth(moves)
+                        .map(|board| (moves, board));
+                }
+            }
+        });
+
+        ui.collapsing("Statistics", |ui| {
+            let stats = &model.egui_data.stats;
+            ui.label(format!("{} games recorded", stats.games().len()));
+            egui::Grid::new("ratings").striped(true).show(ui, |ui| {
+                for heading in ["Player", "Rating", "Games", "Wins"] {
+                    ui.label(heading);
+                }
+                ui.end_row();
+                for rating in stats.ratings() {
+                    ui.label(&rating.name);
+                    ui.label(format!("{:.0}", rating.rating));
+                    ui.label(rating.games.to_string());
+                    ui.label(rating.wins.to_string());
+                    ui.end_row();
+                }
+            });
+            ui.horizontal(|ui| {
+                if ui.button("Export games (CSV)").clicked() {
+                    export_csv("ccheckers_games.csv", stats.games_csv());
+                }
+                if ui.button("Export ratings (CSV)").clicked() {
+                    export_csv("ccheckers_ratings.csv", stats.ratings_csv());
+                }
+            });
+        });
+
+        for turn in model.board.mode.turns() {
+            if let Some(remaining) = model.board.clocks.remaining(turn) {
+                ui.label(format!(
+                    "{}: {}",
+                    model.board.player_name(turn),
+                    clock::format_clock(remaining)
+                ));
+            }
+        }
+
+        for turn in &model.board.forfeited {
+            ui.label(format!("{} forfeited", model.board.player_name(*turn)));
+        }
+
+        match model.board.outcome() {
+            // the puzzles section tells how the puzzle went
+            _ if model.egui_data.puzzle.is_some() => (),
+            Some(Outcome::Won(winner)) => {
+                ui.label(format!("{} has won!", model.board.player_name(winner)));
+            }
+            Some(Outcome::WonOnProgress(winner)) => {
+                ui.label(format!(
+                    "{} has won by being closest to their targets",
+                    model.board.player_name(winner)
+                ));
+            }
+            Some(Outcome::Draw) => {
+                ui.label("The game is drawn");
+            }
+            None if model.board.turn != Turn::None => {
+                ui.label(format!(
+                    "Currently {}'s turn",
+                    model.board.player_name(model.board.turn)
+                ));
+            }
+            None => (),
         }
     });
+
+    if let Some(puzzle) = &model.egui_data.puzzle {
+        puzzle.pass_others(&mut model.board);
+    }
+
+    // puzzles are neither recorded nor summed up like games
+    let game_over = model.board.outcome().is_some()
+        && model.egui_data.puzzle.is_none()
+        && model.editing.is_none();
+    if game_over && !model.egui_data.recorded {
+        let data = &mut model.egui_data;
+        let duration = data
+            .game_started
+            .map_or(Duration::ZERO, |start| start.elapsed());
+        data.stats.record(&model.board, duration);
+        data.recorded = true;
+        let _ = data.stats.save();
+    }
+
+    if game_over && !model.egui_data.results_dismissed {
+        egui::Window::new("Results").show(&ctx, |ui| {
+            standings_grid(ui, "results", &model.board);
+            if ui.button("Close").clicked() {
+                model.egui_data.results_dismissed = true;
+            }
+        });
+    }
+}
+
+/// Places, points and progress of every player
+fn standings_grid(ui: &mut egui::Ui, id: &str, board: &Board) {
+    egui::Grid::new(id).striped(true).show(ui, |ui| {
+        for heading in ["", "Player", "Points", "In target", "Distance left"] {
+            ui.label(heading);
+        }
+        ui.end_row();
+        for standing in board.standings() {
+            ui.label(format!("{}.", standing.place));
+            ui.label(board.player_name(standing.turn));
+            ui.label(standing.score.to_string());
+            ui.label(format!("{}/{}", standing.pieces_in_target, standing.pieces));
+            ui.label(if standing.forfeited {
+                "forfeited".to_string()
+            } else if standing.finished {
+                "finished".to_string()
+            } else {
+                standing.distance_left.to_string()
+            });
+            ui.end_row();
+        }
+    });
+}
+
+/// Chooses who plays a seat: a person, one of the built-in bots, an external engine or someone at
+/// another machine
+fn controller_ui(ui: &mut egui::Ui, seat: usize, controller: &mut Controller) {
+    let kind = match controller {
+        Controller::Human => "Human",
+        Controller::Computer(_) => "Computer",
+        Controller::Engine(_) => "Engine",
+        Controller::Network(_) => "Network",
+    };
+    egui::ComboBox::from_id_source(("controller", seat))
+        .selected_text(kind)
+        .show_ui(ui, |ui| {
+            if ui.selectable_label(kind == "Human", "Human").clicked() {
+                *controller = Controller::Human;
+            }
+            if ui
+                .selectable_label(kind == "Computer", "Computer")
+                .clicked()
+                && kind != "Computer"
+            {
+                *controller = Controller::Computer(COMPUTER_LEVELS[1].0.to_string());
+            }
+            if ui.selectable_label(kind == "Engine", "Engine").clicked() && kind != "Engine" {
+                *controller = Controller::Engine(String::new());
+            }
+            if ui.selectable_label(kind == "Network", "Network").clicked() && kind != "Network" {
+                *controller = Controller::Network(String::new());
+            }
+        });
+
+    match controller {
+        Controller::Human => (),
+        Controller::Computer(name) => {
+            let description = COMPUTER_LEVELS
+                .iter()
+                .find(|(level, _)| *level == name.as_str())
+                .map_or(name.as_str(), |&(_, description)| description);
+            egui::ComboBox::from_id_source(("level", seat))
+                .selected_text(description)
+                .show_ui(ui, |ui| {
+                    for (level, description) in COMPUTER_LEVELS {
+                        ui.selectable_value(name, level.to_string(), description);
+                    }
+                });
+        }
+        Controller::Engine(command) => {
+            ui.add(
+                egui::TextEdit::singleline(command)
+                    .hint_text("Command")
+                    .desired_width(120.0),
+            );
+        }
+        Controller::Network(address) => {
+            ui.add(
+                egui::TextEdit::singleline(address)
+                    .hint_text("host:port")
+                    .desired_width(120.0),
+            );
+        }
+    }
+}
+
+/// A checkbox enabling a number, with a field to edit the number while it is enabled
+fn optional_count(
+    ui: &mut egui::Ui,
+    label: &str,
+    value: &mut Option<usize>,
+    default: usize,
+    range: std::ops::RangeInclusive<usize>,
+) {
+    ui.horizontal(|ui| {
+        let mut enabled = value.is_some();
+        ui.checkbox(&mut enabled, label);
+        match (enabled, value) {
+            (true, Some(n)) => {
+                ui.add(egui::DragValue::new(n).clamp_range(range));
+            }
+            (true, value @ None) => *value = Some(default),
+            (false, value) => *value = None,
+        }
+    });
+}
+
+fn time_control_ui(ui: &mut egui::Ui, control: &mut TimeControl) {
+    let seconds_field = |ui: &mut egui::Ui, label: &str, time: &mut Duration, max: f64| {
+        let mut secs = time.as_secs_f64();
+        ui.horizontal(|ui| {
+            ui.label(label);
+            ui.add(egui::DragValue::new(&mut secs).clamp_range(0.0..=max));
+        });
+        *time = Duration::from_secs_f64(secs);
+    };
+    seconds_field(ui, "Initial time (s)", &mut control.initial, 7200.0);
+    seconds_field(ui, "Increment (s)", &mut control.increment, 600.0);
+
+    let mut per_move = control.per_move.is_some();
+    ui.checkbox(&mut per_move, "Limit time per move");
+    match (per_move, &mut control.per_move) {
+        (true, Some(limit)) => seconds_field(ui, "Per move (s)", limit, 600.0),
+        (true, limit @ None) => *limit = Some(Duration::from_secs(30)),
+        (false, limit) => *limit = None,
+    }
+
+    egui::ComboBox::from_label("When time runs out")
+        .selected_text(format!("{}", control.flag_fall))
+        .show_ui(ui, |ui| {
+            for flag_fall in FlagFall::iter() {
+                ui.selectable_value(&mut control.flag_fall, flag_fall, format!("{flag_fall}"));
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_is_worked_out_again_when_the_moves_change() {
+        let mut board = Board::default();
+        board.reset(Mode::Two);
+        let mut replay = Replay::default();
+        assert_eq!(replay.of(&board).len(), 1);
+
+        let paths = board.legal_paths();
+        assert!(board.play_path(&paths[0]));
+        assert!(replay.of(&board) == board.replay());
+        assert_eq!(replay.of(&board).len(), 2);
+
+        // another move in its place
+        board.undo_move();
+        assert!(board.play_path(&paths[1]));
+        assert!(replay.of(&board) == board.replay());
+    }
 }
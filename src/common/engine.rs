@@ -0,0 +1,449 @@
+//! Lets an external program play a seat by talking a line-based protocol over its stdin and
+//! stdout. Cells are written as in [`Board::cell_name`], players and pieces as numbers from 1 to 6.
+//!
+//! ```text
+//! app    -> engine: ccheckers 1
+//! engine -> app:    ready
+//! app    -> engine: position <two|three|six> <player to move> <cell>:<piece> <cell>:<piece> ...
+//! app    -> engine: go <milliseconds available> <id>
+//! engine -> app:    move <id of the go> <cell> <cell> ...
+//! app    -> engine: illegal | timeout   (only if the move was not accepted)
+//! app    -> engine: quit
+//! ```
+//!
+//! Any other lines the engine prints are ignored, so it is free to log to stdout. When the engine
+//! answers with an illegal move, does not answer in time or dies, the seat is played by a
+//! [`RandomBot`] for that move instead. Every `go` carries a new id, so a `move` sent after its
+//! time ran out names an earlier `go` and is thrown away rather than taken as the answer to the
+//! next one.
+//!
+//! A seat can also be played from another machine: [`NetworkBot`] connects to an address over TCP
+//! and speaks the same protocol there, e.g. to an engine behind a server or a program relaying the
+//! moves of a person.
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    process::{Child, Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use nannou::prelude::*;
+use strum::IntoEnumIterator;
+
+use super::{
+    ai::{Bot, RandomBot},
+    board::{cells::Cells, Board},
+    player::{Piece, Turn},
+};
+
+pub const PROTOCOL_VERSION: u32 = 1;
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct ExternalBot {
+    /// The engine's process, unless it is at the other end of a connection
+    child: Option<Child>,
+    input: Box<dyn Write + Send>,
+    lines: Receiver<String>,
+    timeout: Duration,
+    fallback: RandomBot,
+    /// Why the last move was not the engine's own, until it is reported
+    problem: Option<String>,
+    /// Whether the engine said it is ready. It is waited for when the engine is first asked for a
+    /// move, on the thread the bot thinks on, so that starting an engine never blocks.
+    ready: bool,
+    /// Id of the last `go` sent
+    request: u64,
+}
+
+impl ExternalBot {
+    /// Starts the engine and greets it, without waiting for it to report that it is ready.
+    /// `command` is split on whitespace into the program and its arguments.
+    pub fn spawn(command: &str, timeout: Duration) -> Result<Self, String> {
+        let mut words = command.split_whitespace();
+        let program = words.next().ok_or("empty engine command")?;
+        let mut child = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("could not start {program}: {e}"))?;
+
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        Ok(Self::talk(Some(child), Box::new(stdin), stdout, timeout))
+    }
+
+    /// Connects to an engine listening at the address, e.g. `example.com:4000`, giving up once
+    /// the timeout runs out. Like [`ExternalBot::spawn`], it does not wait for the engine to
+    /// report that it is ready.
+    pub fn connect(address: &str, timeout: Duration) -> Result<Self, String> {
+        let unreachable = |e: std::io::Error| format!("could not connect to {address}: {e}");
+        let socket = address
+            .to_socket_addrs()
+            .map_err(unreachable)?
+            .next()
+            .ok_or_else(|| format!("{address} has no address"))?;
+        let stream = TcpStream::connect_timeout(&socket, timeout).map_err(unreachable)?;
+        let output = stream.try_clone().map_err(unreachable)?;
+        Ok(Self::talk(None, Box::new(stream), output, timeout))
+    }
+
+    /// Reads the lines of the engine on another thread and greets it
+    fn talk(
+        child: Option<Child>,
+        input: Box<dyn Write + Send>,
+        output: impl Read + Send + 'static,
+        timeout: Duration,
+    ) -> Self {
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(output).lines() {
+                match line {
+                    Ok(line) => {
+                        if sender.send(line).is_err() {
+                            break;
+                        }
+                    }
+                    Err(_) => break,
+                }
+            }
+        });
+
+        let mut bot = Self {
+            child,
+            input,
+            lines,
+            timeout,
+            fallback: RandomBot::new(0),
+            problem: None,
+            ready: false,
+            request: 0,
+        };
+        bot.send(&format!("ccheckers {PROTOCOL_VERSION}"));
+        bot
+    }
+
+    fn send(&mut self, line: &str) {
+        // a dead engine is noticed when waiting for its answer
+        let _ = writeln!(self.input, "{line}").and_then(|_| self.input.flush());
+    }
+
+    /// Waits for the first line the parser accepts, giving up once the timeout runs out
+    fn expect<T>(&mut self, mut parse: impl FnMut(&str) -> Option<T>) -> Result<T, String> {
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            match self.lines.recv_timeout(left) {
+                Ok(line) => {
+                    if let Some(t) = parse(&line) {
+                        break Ok(t);
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => break Err("timeout".to_string()),
+                Err(RecvTimeoutError::Disconnected) => break Err("engine exited".to_string()),
+            }
+        }
+    }
+
+    /// Writes out the position as sent with the `position` command
+    pub fn position_command(board: &Board) -> String {
+        let number = |n: Option<usize>| n.unwrap_or(0).to_string();
+        let mut command = format!(
+            "position {} {}",
+            board.mode.to_string().to_lowercase(),
+            number(Turn::iter().position(|t| t == board.turn)),
+        );
+        let mut pieces = board.pieces().collect::<Vec<_>>();
+        pieces.sort_by_key(|(pos, _)| (pos.x, pos.y));
+        for (pos, piece) in pieces {
+            let piece = number(Piece::iter().position(|p| p == piece));
+            command += &format!(" {}:{piece}", Board::cell_name(pos));
+        }
+        command
+    }
+
+    /// The id of the `go` a `move` line answers
+    fn answers(line: &str) -> Option<u64> {
+        let mut words = line.split_whitespace();
+        (words.next()? == "move").then_some(())?;
+        words.next()?.parse().ok()
+    }
+
+    /// Reads the path out of a `move` line. Cells off the board make the whole move unreadable.
+    pub fn parse_move(line: &str) -> Option<Vec<IVec2>> {
+        Self::answers(line)?;
+        line.split_whitespace()
+            .skip(2)
+            .map(|word| Board::parse_cell(word).filter(|&cell| Cells::index(cell).is_some()))
+            .collect()
+    }
+}
+
+impl Bot for ExternalBot {
+    fn choose(&mut self, board: &Board) -> Option<Vec<IVec2>> {
+        if !self.ready {
+            match self.expect(|line| (line.trim() == "ready").then_some(())) {
+                Ok(()) => self.ready = true,
+                Err(e) => {
+                    self.problem = Some(format!("the engine did not get ready: {e}"));
+                    return self.fallback.choose(board);
+                }
+            }
+        }
+
+        self.request += 1;
+        let request = self.request;
+        self.send(&Self::position_command(board));
+        self.send(&format!("go {} {request}", self.timeout.as_millis()));
+
+        // moves answering an earlier go came too late and are skipped
+        let (reply, problem) = match self
+            .expect(|line| (Self::answers(line) == Some(request)).then(|| line.to_string()))
+        {
+            Ok(line) => match Self::parse_move(&line) {
+                Some(path) if board.clone().play_path(&path) => return Some(path),
+                _ => (
+                    "illegal",
+                    format!("the engine played an illegal move: {line}"),
+                ),
+            },
+            Err(e) => ("timeout", format!("the engine failed to move: {e}")),
+        };
+        self.send(reply);
+        self.problem = Some(problem);
+        self.fallback.choose(board)
+    }
+
+    fn take_problem(&mut self) -> Option<String> {
+        self.problem.take()
+    }
+}
+
+impl Drop for ExternalBot {
+    fn drop(&mut self) {
+        self.send("quit");
+        if let Some(child) = &mut self.child {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// Plays a seat from another machine through an [`ExternalBot`] connected over TCP. It connects
+/// when it is first asked for a move, on the thread the bot thinks on, and tries again on later
+/// moves for as long as it cannot.
+pub struct NetworkBot {
+    address: String,
+    timeout: Duration,
+    bot: Option<ExternalBot>,
+    fallback: RandomBot,
+    problem: Option<String>,
+}
+
+impl NetworkBot {
+    pub fn new(address: &str, timeout: Duration) -> Self {
+        Self {
+            address: address.trim().to_string(),
+            timeout,
+            bot: None,
+            fallback: RandomBot::new(0),
+            problem: None,
+        }
+    }
+}
+
+impl Bot for NetworkBot {
+    fn choose(&mut self, board: &Board) -> Option<Vec<IVec2>> {
+        if self.bot.is_none() {
+            match ExternalBot::connect(&self.address, self.timeout) {
+                Ok(bot) => self.bot = Some(bot),
+                Err(e) => {
+                    self.problem = Some(e);
+                    return self.fallback.choose(board);
+                }
+            }
+        }
+        self.bot.as_mut()?.choose(board)
+    }
+
+    fn take_problem(&mut self) -> Option<String> {
+        self.problem
+            .take()
+            .or_else(|| self.bot.as_mut()?.take_problem())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use super::super::{board::editing::Brush, player::Mode};
+    use super::*;
+
+    fn started() -> Board {
+        let mut board = Board::default();
+        board.reset(Mode::Two);
+        board
+    }
+
+    /// Starts a shell script as the engine. It says it is ready, then answers every `go` with
+    /// `move <id> <cells>` after sleeping for the given number of seconds.
+    #[cfg(unix)]
+    fn engine(name: &str, delay: f32, cells: &str, timeout: Duration) -> ExternalBot {
+        let script = format!(
+            "echo ready\n\
+             while read command time id; do\n\
+             case $command in\n\
+             go) sleep {delay}; echo \"move $id {cells}\" ;;\n\
+             quit) exit ;;\n\
+             esac\n\
+             done\n"
+        );
+        let path =
+            std::env::temp_dir().join(format!("ccheckers_engine_{name}_{}.sh", std::process::id()));
+        std::fs::write(&path, script).unwrap();
+        ExternalBot::spawn(&format!("sh {}", path.display()), timeout).unwrap()
+    }
+
+    #[test]
+    fn positions_list_every_piece() {
+        let mut board = started();
+        board.clear();
+        board.paint(IVec2::ZERO, Brush::Paint(Piece::Player1));
+        board.paint(ivec2(0, -1), Brush::Paint(Piece::Player4));
+        board.paint(IVec2::X, Brush::Paint(Piece::Player2));
+        board.turn = Turn::Player4;
+        assert_eq!(
+            ExternalBot::position_command(&board),
+            "position two 4 i8:4 i9:1 j9:2"
+        );
+    }
+
+    #[test]
+    fn moves_are_read_only_when_well_formed_and_on_the_board() {
+        assert_eq!(
+            ExternalBot::parse_move("move 3 i9 i11 g11"),
+            Some(vec![IVec2::ZERO, ivec2(0, 2), ivec2(-2, 2)])
+        );
+        assert_eq!(ExternalBot::answers("move 3 i9 i11"), Some(3));
+        for line in [
+            "",
+            "move",
+            "move i9 i11",
+            "moves 3 i9 i11",
+            "move 3 i9 z9",
+            "move 3 i9 i",
+            "move 3 i9 i18",
+            // on the grid of names, but off the star
+            "move 3 m13 a1",
+            "move 3 i9 a1",
+        ] {
+            assert_eq!(ExternalBot::parse_move(line), None, "{line}");
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn legal_moves_of_the_engine_are_played() {
+        let board = started();
+        let path = board.legal_paths().remove(0);
+        let cells = path.iter().map(|&cell| Board::cell_name(cell)).join(" ");
+        let mut bot = engine("legal", 0.0, &cells, DEFAULT_TIMEOUT);
+        assert_eq!(bot.choose(&board), Some(path.clone()));
+        assert_eq!(bot.take_problem(), None);
+        assert_eq!(bot.choose(&board), Some(path));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn illegal_moves_are_replaced_by_legal_ones() {
+        let board = started();
+        // moving out of the empty middle, and moving off the board
+        for (name, cells) in [("illegal", "i9 i10"), ("off_board", "m13 a1")] {
+            let mut bot = engine(name, 0.0, cells, DEFAULT_TIMEOUT);
+            let path = bot.choose(&board).unwrap();
+            assert!(board.clone().play_path(&path));
+            assert!(bot.take_problem().unwrap().contains("illegal move"));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn moves_sent_too_late_are_thrown_away() {
+        let board = started();
+        let path = board.legal_paths().remove(0);
+        let cells = path.iter().map(|&cell| Board::cell_name(cell)).join(" ");
+        let mut bot = engine("late", 0.8, &cells, Duration::from_millis(500));
+        for _ in 0..2 {
+            // the second time, the answer to the first go arrives while waiting, and is not taken
+            let chosen = bot.choose(&board).unwrap();
+            assert!(board.clone().play_path(&chosen));
+            assert!(bot.take_problem().unwrap().contains("timeout"));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn engines_are_waited_for_on_their_first_move() {
+        let path =
+            std::env::temp_dir().join(format!("ccheckers_engine_silent_{}.sh", std::process::id()));
+        std::fs::write(&path, "while read line; do :; done\n").unwrap();
+        let timeout = Duration::from_millis(300);
+        let started_at = Instant::now();
+        let mut bot = ExternalBot::spawn(&format!("sh {}", path.display()), timeout).unwrap();
+        assert!(started_at.elapsed() < timeout);
+
+        let board = started();
+        let chosen = bot.choose(&board).unwrap();
+        assert!(board.clone().play_path(&chosen));
+        assert!(bot.take_problem().unwrap().contains("did not get ready"));
+    }
+
+    #[test]
+    fn network_seats_play_the_moves_sent_over_the_connection() {
+        let board = started();
+        let path = board.legal_paths().remove(0);
+        let cells = path.iter().map(|&cell| Board::cell_name(cell)).join(" ");
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let remote = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut output = stream.try_clone().unwrap();
+            let mut lines = BufReader::new(stream).lines().map(Result::unwrap);
+            assert_eq!(
+                lines.next().unwrap(),
+                format!("ccheckers {PROTOCOL_VERSION}")
+            );
+            writeln!(output, "ready").unwrap();
+            assert!(lines.next().unwrap().starts_with("position two 1 "));
+            let go = lines.next().unwrap();
+            let id = go.split_whitespace().nth(2).unwrap();
+            writeln!(output, "move {id} {cells}").unwrap();
+            assert_eq!(lines.next().unwrap(), "quit");
+        });
+
+        let mut bot = NetworkBot::new(&address, DEFAULT_TIMEOUT);
+        assert_eq!(bot.choose(&board), Some(path));
+        assert_eq!(bot.take_problem(), None);
+        drop(bot);
+        remote.join().unwrap();
+    }
+
+    #[test]
+    fn unreachable_network_seats_are_played_by_the_fallback() {
+        // a port nothing listens on any more
+        let address = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .to_string();
+        let board = started();
+        let mut bot = NetworkBot::new(&address, Duration::from_millis(300));
+        let chosen = bot.choose(&board).unwrap();
+        assert!(board.clone().play_path(&chosen));
+        assert!(bot.take_problem().unwrap().contains("could not connect"));
+    }
+}
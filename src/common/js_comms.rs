@@ -1,14 +1,27 @@
-use super::{player::Mode, Model};
+use super::{
+    board::Board,
+    clock::{self, FlagFall, TimeControl},
+    player::Mode,
+    theme::{Palette, Theme},
+    Model,
+};
 use once_cell::sync::Lazy;
 use std::{sync::Mutex, time::Duration};
 use wasm_bindgen::prelude::*;
 
 static SIGNAL_RESET: Lazy<Mutex<(Mode, bool)>> = Lazy::new(|| Mutex::new((Mode::default(), false)));
 static SIGNAL_LOAD: Lazy<Mutex<Option<SaveRequest>>> = Lazy::new(|| Mutex::new(None));
+static SIGNAL_HINT: Lazy<Mutex<bool>> = Lazy::new(|| Mutex::new(false));
+static TIME_CONTROL: Lazy<Mutex<Option<TimeControl>>> = Lazy::new(|| Mutex::new(None));
+static CLOCK_TEXT: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new(String::new()));
+static SIGNAL_THEME: Lazy<Mutex<Option<Theme>>> = Lazy::new(|| Mutex::new(None));
+static SIGNAL_FOLLOW_TURN: Lazy<Mutex<Option<bool>>> = Lazy::new(|| Mutex::new(None));
+static SIGNAL_LABELS: Lazy<Mutex<Option<bool>>> = Lazy::new(|| Mutex::new(None));
 
 pub enum SaveRequest {
     RequestLoad { data: String },
     RequestSave,
+    RequestSvg,
     SaveData { data: String },
 }
 
@@ -21,8 +34,18 @@ pub fn request_load(data: String) {
 
 #[wasm_bindgen]
 pub async fn request_store() -> String {
+    await_save_data(SaveRequest::RequestSave).await
+}
+
+#[wasm_bindgen]
+pub async fn request_svg() -> String {
+    await_save_data(SaveRequest::RequestSvg).await
+}
+
+/// Hands the request to the app and waits for it to respond with the data
+async fn await_save_data(request: SaveRequest) -> String {
     if let Ok(mut mu_guard) = Lazy::force(&SIGNAL_LOAD).lock() {
-        *mu_guard = Some(SaveRequest::RequestSave)
+        *mu_guard = Some(request)
     }
 
     loop {
@@ -56,6 +79,126 @@ pub fn recieve_reset() -> Option<Mode> {
         })
 }
 
+#[wasm_bindgen]
+pub fn signal_hint() {
+    if let Ok(mut mu_guard) = Lazy::force(&SIGNAL_HINT).lock() {
+        *mu_guard = true
+    }
+}
+
+pub fn recieve_hint() -> bool {
+    Lazy::get(&SIGNAL_HINT)
+        .and_then(|mu| mu.try_lock().ok())
+        .map(|mut mu_guard| std::mem::take(&mut *mu_guard))
+        .unwrap_or(false)
+}
+
+/// Changes how the board is drawn. The page remembers the choice and sends it again on load.
+#[wasm_bindgen]
+pub fn set_theme(palette: Palette, dark: bool, markers: bool) {
+    if let Ok(mut mu_guard) = Lazy::force(&SIGNAL_THEME).lock() {
+        *mu_guard = Some(Theme {
+            palette,
+            dark,
+            markers,
+        })
+    }
+}
+
+pub fn recieve_theme() -> Option<Theme> {
+    Lazy::get(&SIGNAL_THEME)
+        .and_then(|mu| mu.try_lock().ok())
+        .and_then(|mut mu_guard| mu_guard.take())
+}
+
+/// Whether to turn the board so that the player to move sits at the bottom
+#[wasm_bindgen]
+pub fn set_follow_turn(follow_turn: bool) {
+    if let Ok(mut mu_guard) = Lazy::force(&SIGNAL_FOLLOW_TURN).lock() {
+        *mu_guard = Some(follow_turn)
+    }
+}
+
+pub fn recieve_follow_turn() -> Option<bool> {
+    Lazy::get(&SIGNAL_FOLLOW_TURN)
+        .and_then(|mu| mu.try_lock().ok())
+        .and_then(|mut mu_guard| mu_guard.take())
+}
+
+/// Whether to write the name of each cell on it, and describe the cell under the cursor
+#[wasm_bindgen]
+pub fn set_labels(labels: bool) {
+    if let Ok(mut mu_guard) = Lazy::force(&SIGNAL_LABELS).lock() {
+        *mu_guard = Some(labels)
+    }
+}
+
+pub fn recieve_labels() -> Option<bool> {
+    Lazy::get(&SIGNAL_LABELS)
+        .and_then(|mu| mu.try_lock().ok())
+        .and_then(|mut mu_guard| mu_guard.take())
+}
+
+/// Sets the time control used from the next reset on. A zero initial time turns the clocks off,
+/// and a zero per-move limit means there is none.
+#[wasm_bindgen]
+pub fn set_time_control(
+    minutes: f64,
+    increment_secs: f64,
+    per_move_secs: f64,
+    flag_fall: FlagFall,
+) {
+    if let Ok(mut mu_guard) = Lazy::force(&TIME_CONTROL).lock() {
+        *mu_guard = (minutes > 0.0).then(|| TimeControl {
+            initial: Duration::from_secs_f64(minutes * 60.0),
+            increment: Duration::from_secs_f64(increment_secs.max(0.0)),
+            per_move: (per_move_secs > 0.0).then(|| Duration::from_secs_f64(per_move_secs)),
+            flag_fall,
+        })
+    }
+}
+
+pub fn time_control() -> Option<TimeControl> {
+    Lazy::get(&TIME_CONTROL)
+        .and_then(|mu| mu.lock().ok())
+        .and_then(|mu_guard| *mu_guard)
+}
+
+/// The clocks as shown on the page, one player per line
+#[wasm_bindgen]
+pub fn clock_text() -> String {
+    Lazy::get(&CLOCK_TEXT)
+        .and_then(|mu| mu.lock().ok())
+        .map(|mu_guard| mu_guard.clone())
+        .unwrap_or_default()
+}
+
+pub fn publish_clocks(board: &Board) {
+    let text = board
+        .mode
+        .turns()
+        .filter_map(|turn| {
+            let remaining = board.clocks.remaining(turn)?;
+            let marker = if turn == board.turn { ">" } else { " " };
+            Some(format!(
+                "{marker} {}: {}",
+                board.player_name(turn),
+                clock::format_clock(remaining)
+            ))
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let text = match board.outcome() {
+        Some(_) => format!("{text}\n\nResults:\n{}", board.standings_text())
+            .trim_start()
+            .to_string(),
+        None => text,
+    };
+    if let Ok(mut mu_guard) = Lazy::force(&CLOCK_TEXT).try_lock() {
+        *mu_guard = text
+    }
+}
+
 pub fn respond_load_request(model: &mut Model) {
     if let Some(mut mu_guard) = Lazy::get(&SIGNAL_LOAD).and_then(|mu| mu.try_lock().ok()) {
         match std::mem::take(&mut *mu_guard) {
@@ -67,6 +210,11 @@ pub fn respond_load_request(model: &mut Model) {
                     data: ron::to_string(&model.board).unwrap(),
                 })
             }
+            Some(SaveRequest::RequestSvg) => {
+                *mu_guard = Some(SaveRequest::SaveData {
+                    data: model.board.to_svg(800, &model.theme),
+                })
+            }
             None => (),
             x => {
                 *mu_guard = x;
@@ -1,14 +1,13 @@
 use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter};
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
-use nannou::{
-    color::encoding::{srgb, Linear},
-    prelude::*,
-};
-use strum_macros::{Display, EnumIter};
 
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
-#[derive(Copy, Clone, PartialEq, Eq, Default, Debug, EnumIter, Display, Serialize, Deserialize)]
+#[derive(
+    Copy, Clone, PartialEq, Eq, Hash, Default, Debug, EnumIter, Display, Serialize, Deserialize,
+)]
 pub enum Mode {
     #[default]
     Two,
@@ -16,7 +15,9 @@ pub enum Mode {
     Six,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Default, Display, Serialize, Deserialize)]
+#[derive(
+    Copy, Clone, PartialEq, Eq, Hash, Default, Debug, Display, EnumIter, Serialize, Deserialize,
+)]
 pub enum Turn {
     #[default]
     None,
@@ -64,7 +65,7 @@ impl Turn {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Default, Display, EnumIter, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug, Display, EnumIter, Serialize, Deserialize)]
 pub enum Piece {
     #[default]
     None,
@@ -76,29 +77,66 @@ pub enum Piece {
     Player6,
 }
 
-impl From<Piece> for rgb::Rgb<Linear<srgb::Srgb>> {
-    fn from(value: Piece) -> Self {
-        match value {
-            Piece::None => DARKSLATEGRAY,
-            Piece::Player1 => DARKORCHID,
-            Piece::Player2 => FUCHSIA,
-            Piece::Player3 => YELLOWGREEN,
-            Piece::Player4 => MAROON,
-            Piece::Player5 => MIDNIGHTBLUE,
-            Piece::Player6 => OLIVE,
-        }
-        .into_format::<f32>()
-        .into_linear()
-    }
+/// Who plays one of the turns of a game, and how they are shown
+#[derive(Clone, PartialEq, Eq, Default, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Seat {
+    pub name: String,
+    /// A short symbol shown before the name, such as an emoji or initials
+    pub avatar: String,
+    /// Color of the player's pieces, instead of the one the theme gives them
+    pub color: Option<[u8; 3]>,
+    pub controller: Controller,
+}
+
+/// Where the moves of a seat come from
+#[derive(Clone, PartialEq, Eq, Default, Debug, Serialize, Deserialize)]
+pub enum Controller {
+    /// Someone clicking on the board
+    #[default]
+    Human,
+    /// A built-in bot, by the name [`super::ai::bot_from_name`] knows it by
+    Computer(String),
+    /// An external engine, by the command starting it
+    Engine(String),
+    /// Someone or something at another machine, by the address to connect to, e.g.
+    /// `example.com:4000`
+    Network(String),
 }
 
 impl Piece {
     pub fn is_none(&self) -> bool {
         matches!(self, Piece::None)
     }
+
+    /// The piece whose home region lies across the board from this one's
+    pub fn opposite(&self) -> Piece {
+        match self {
+            Piece::None => Piece::None,
+            Piece::Player1 => Piece::Player4,
+            Piece::Player2 => Piece::Player5,
+            Piece::Player3 => Piece::Player6,
+            Piece::Player4 => Piece::Player1,
+            Piece::Player5 => Piece::Player2,
+            Piece::Player6 => Piece::Player3,
+        }
+    }
 }
 
 impl Mode {
+    pub fn players(&self) -> usize {
+        match self {
+            Mode::Two => 2,
+            Mode::Three => 3,
+            Mode::Six => 6,
+        }
+    }
+
+    /// Every player taking part in this mode, in turn order
+    pub fn turns(&self) -> impl Iterator<Item = Turn> {
+        Turn::iter().skip(1).take(self.players())
+    }
+
     pub fn next_turn(&self, turn: Turn) -> Turn {
         match self {
             Mode::Two => match turn {
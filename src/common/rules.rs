@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumIter};
+
+/// Optional rules against spoiling, i.e. parking pieces in one's own home so that an opponent
+/// can never fill it, and against games that never end. All of them are off by default, except
+/// playing on to decide every place.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rules {
+    /// A player whose target contains opposing pieces has finished once every other cell of the
+    /// target is filled with their own pieces.
+    pub anti_spoiling: bool,
+    /// Keeps playing after the first player finishes, until only one is left, so that every
+    /// place is decided
+    pub play_on: bool,
+    /// Forfeits players who keep too many pieces at home for too long
+    pub home_limit: Option<HomeLimit>,
+    /// Ends the game once the same position, with the same player to move, has come up this
+    /// many times
+    pub repetition: Option<usize>,
+    /// Ends the game after this many moves in total
+    pub move_limit: Option<usize>,
+    /// How a game ended by repetition or the move limit is scored
+    pub limit_result: LimitResult,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            anti_spoiling: false,
+            play_on: true,
+            home_limit: None,
+            repetition: None,
+            move_limit: None,
+            limit_result: LimitResult::default(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug, EnumIter, Display, Serialize, Deserialize)]
+pub enum LimitResult {
+    #[default]
+    Draw,
+    /// The player with the least distance left to their targets wins
+    Progress,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct HomeLimit {
+    /// Number of the player's own moves after which the limit is checked
+    pub after_moves: usize,
+    /// Most pieces the player may still have in their home regions at that point
+    pub max_pieces: usize,
+}
+
+impl Default for HomeLimit {
+    fn default() -> Self {
+        Self {
+            after_moves: 30,
+            max_pieces: 0,
+        }
+    }
+}
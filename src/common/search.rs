@@ -0,0 +1,282 @@
+use nannou::prelude::*;
+
+use super::{
+    ai::Bot,
+    board::{endgame::RaceBudget, zobrist, Board},
+    player::Turn,
+};
+
+/// Value of a won position, and minus that of a lost one
+pub const WIN: i32 = 1_000_000;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Bound {
+    Exact,
+    /// The real value is at least the stored one
+    Lower,
+    /// The real value is at most the stored one
+    Upper,
+}
+
+#[derive(Clone)]
+struct Entry {
+    key: u64,
+    depth: u32,
+    value: i32,
+    bound: Bound,
+    best: Option<Vec<IVec2>>,
+}
+
+/// Remembers the results of earlier searches by position hash, so that positions reached through
+/// different move orders (or searched again on a later turn) are not searched twice
+pub struct TranspositionTable {
+    entries: Vec<Option<Entry>>,
+}
+
+impl TranspositionTable {
+    /// Creates a table with `1 << bits` entries. Colliding positions replace each other.
+    pub fn new(bits: u32) -> Self {
+        Self {
+            entries: vec![None; 1 << bits],
+        }
+    }
+
+    fn slot(&self, key: u64) -> usize {
+        (key as usize) & (self.entries.len() - 1)
+    }
+
+    fn get(&self, key: u64) -> Option<&Entry> {
+        self.entries[self.slot(key)]
+            .as_ref()
+            .filter(|entry| entry.key == key)
+    }
+
+    fn store(&mut self, entry: Entry) {
+        let slot = self.slot(entry.key);
+        // prefer keeping the deeper result of the same position
+        let keep = self.entries[slot]
+            .as_ref()
+            .is_some_and(|old| old.key == entry.key && old.depth > entry.depth);
+        if !keep {
+            self.entries[slot] = Some(entry);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.iter_mut().for_each(|entry| *entry = None);
+    }
+}
+
+/// How good the position is for the given player: how much further the opponents are from their
+/// targets on average, compared to the player
+pub fn evaluate(board: &Board, player: Turn) -> i32 {
+    match board.outcome().map(|outcome| outcome.winner()) {
+        Some(Some(winner)) if winner == player => return WIN,
+        Some(Some(_)) => return -WIN,
+        Some(None) => return 0,
+        None => (),
+    }
+
+    let opponents = board.mode.turns().filter(|&turn| turn != player);
+    let (total, count) = opponents.fold((0, 0), |(total, count), turn| {
+        (total + board.distance_to_target(turn), count + 1)
+    });
+    total / count.max(1) - board.distance_to_target(player)
+}
+
+/// Looks a few moves ahead with alpha-beta search. With more than two players, every opponent is
+/// assumed to play against the searching player.
+pub struct SearchBot {
+    depth: u32,
+    table: TranspositionTable,
+}
+
+impl SearchBot {
+    pub fn new(depth: u32) -> Self {
+        Self {
+            depth: depth.max(1),
+            table: TranspositionTable::new(18),
+        }
+    }
+
+    /// How good the position is for the player, looking the given number of moves ahead
+    pub fn value(&mut self, board: &Board, player: Turn, depth: u32) -> i32 {
+        self.search(&mut board.clone(), player, depth, -WIN - 1, WIN + 1)
+            .0
+    }
+
+    /// Searched paths, with the move the table remembers as best first and the rest ordered by
+    /// how far they bring their piece
+    fn ordered_paths(&self, board: &Board, key: u64) -> Vec<Vec<IVec2>> {
+        let mut paths = board.legal_paths();
+        paths.sort_by_cached_key(|path| {
+            let piece = board.get(&path[0]).unwrap();
+            let tip = Board::target_tip(piece).unwrap();
+            Board::hex_distance(*path.last().unwrap(), tip) - Board::hex_distance(path[0], tip)
+        });
+        if let Some(best) = self.table.get(key).and_then(|entry| entry.best.as_ref()) {
+            if let Some(i) = paths.iter().position(|path| path == best) {
+                let best = paths.remove(i);
+                paths.insert(0, best);
+            }
+        }
+        paths
+    }
+
+    fn search(
+        &mut self,
+        board: &mut Board,
+        player: Turn,
+        depth: u32,
+        mut alpha: i32,
+        mut beta: i32,
+    ) -> (i32, Option<Vec<IVec2>>) {
+        if depth == 0 || board.outcome().is_some() {
+            return (evaluate(board, player), None);
+        }
+
+        // the same position is worth something else to every player
+        let key = board.hash() ^ zobrist::turn_key(player).rotate_left(17);
+        if let Some(entry) = self.table.get(key).filter(|entry| entry.depth >= depth) {
+            let cutoff = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => entry.value >= beta,
+                Bound::Upper => entry.value <= alpha,
+            };
+            if cutoff {
+                return (entry.value, entry.best.clone());
+            }
+        }
+
+        let maximizing = board.turn == player;
+        let (original_alpha, original_beta) = (alpha, beta);
+        let mut best: (i32, Option<Vec<IVec2>>) =
+            (if maximizing { -WIN - 1 } else { WIN + 1 }, None);
+        for path in self.ordered_paths(board, key) {
+            let made = board.make_move(&path);
+            let (value, _) = self.search(board, player, depth - 1, alpha, beta);
+            board.unmake_move(made);
+
+            if maximizing && value > best.0 || !maximizing && value < best.0 {
+                best = (value, Some(path));
+            }
+            if maximizing {
+                alpha = alpha.max(value);
+            } else {
+                beta = beta.min(value);
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        if best.1.is_none() {
+            // nothing to play, so the turn passes
+            let made = board.make_move(&[]);
+            best.0 = self.search(board, player, depth - 1, alpha, beta).0;
+            board.unmake_move(made);
+        }
+
+        let bound = if best.0 <= original_alpha {
+            Bound::Upper
+        } else if best.0 >= original_beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.table.store(Entry {
+            key,
+            depth,
+            value: best.0,
+            bound,
+            best: best.1.clone(),
+        });
+        best
+    }
+}
+
+impl Bot for SearchBot {
+    fn choose(&mut self, board: &Board) -> Option<Vec<IVec2>> {
+        // once the players have passed each other, all that counts is finishing in fewer moves
+        if board.is_race() {
+            let line = board.shortest_finish(board.turn, RaceBudget::SEARCH);
+            if let Some(path) = line.and_then(|line| line.into_iter().next()) {
+                return Some(path);
+            }
+        }
+
+        // moves are made and taken back on a single copy of the board
+        let (mut board, player) = (board.clone(), board.turn);
+        let mut best = None;
+        // deepen step by step so that earlier iterations order the moves of later ones
+        for depth in 1..=self.depth {
+            best = self
+                .search(&mut board, player, depth, -WIN - 1, WIN + 1)
+                .1
+                .or(best);
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use strum::IntoEnumIterator;
+
+    use super::super::player::Mode;
+    use super::*;
+
+    /// Plain minimax without pruning or a table, to check the search against
+    fn minimax(board: &mut Board, player: Turn, depth: u32) -> i32 {
+        if depth == 0 || board.outcome().is_some() {
+            return evaluate(board, player);
+        }
+        let maximizing = board.turn == player;
+        let paths = board.legal_paths();
+        let values = if paths.is_empty() {
+            vec![vec![]]
+        } else {
+            paths
+        }
+        .into_iter()
+        .map(|path| {
+            let made = board.make_move(&path);
+            let value = minimax(board, player, depth - 1);
+            board.unmake_move(made);
+            value
+        });
+        if maximizing {
+            values.max().unwrap()
+        } else {
+            values.min().unwrap()
+        }
+    }
+
+    #[test]
+    fn table_bounds_keep_the_minimax_value() {
+        for mode in Mode::iter() {
+            let mut board = Board::default();
+            board.reset(mode);
+            // the bot is kept between positions so that later searches run into stored bounds,
+            // including ones from narrower windows
+            let mut bot = SearchBot::new(2);
+            for _ in 0..6 {
+                let player = board.turn;
+                for depth in 1..=2 {
+                    let expected = minimax(&mut board.clone(), player, depth);
+                    let full = bot.search(&mut board, player, depth, -WIN - 1, WIN + 1).0;
+                    assert_eq!(full, expected, "{mode} players at depth {depth}");
+                    // a window around the value still finds it, and windows beside it bound it
+                    let narrow = bot.search(&mut board, player, depth, expected - 1, expected + 1);
+                    assert_eq!(narrow.0, expected);
+                    assert!(bot.search(&mut board, player, depth, expected, WIN + 1).0 <= expected);
+                    assert!(
+                        bot.search(&mut board, player, depth, -WIN - 1, expected).0 >= expected
+                    );
+                }
+                let path = bot.choose(&board).unwrap();
+                assert!(board.play_path(&path));
+            }
+        }
+    }
+}
@@ -0,0 +1,300 @@
+//! Finished games kept between sessions, and the ratings of the players worked out from them
+
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    board::{Board, Outcome},
+    player::{Mode, Turn},
+};
+
+/// Rating of a player who has not played yet
+const START_RATING: f64 = 1500.0;
+/// Most a rating can change by in one game
+const K_FACTOR: f64 = 32.0;
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct GameResult {
+    /// Seconds since the Unix epoch
+    pub finished_at: u64,
+    pub mode: Mode,
+    pub players: Vec<String>,
+    /// Place of each of the players, from 1; players who drew share a place
+    pub places: Vec<usize>,
+    pub moves: usize,
+    pub duration_secs: f64,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Rating {
+    pub name: String,
+    pub rating: f64,
+    pub games: u32,
+    /// Games the player finished first in without sharing the place
+    pub wins: u32,
+}
+
+#[derive(Clone, PartialEq, Default, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Stats {
+    games: Vec<GameResult>,
+    /// The ratings after all of the games, best first, worked out whenever a game is added
+    #[serde(skip)]
+    ratings: Vec<Rating>,
+}
+
+/// Name the player of the seat is rated under: the one given to the seat, or the seat's own
+fn rated_name(board: &Board, turn: Turn) -> String {
+    match board.seat(turn) {
+        Some(seat) if !seat.name.trim().is_empty() => seat.name.trim().to_string(),
+        _ => turn.to_string(),
+    }
+}
+
+/// Quotes a field of a CSV file if it needs to be
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Elo ratings of everyone who played, best first. A game of several players counts as a game
+/// between every two of them, each decided by who placed better, with the changes scaled down so
+/// that a game moves a rating by at most as much as a game of two would.
+fn rate(games: &[GameResult]) -> Vec<Rating> {
+    let mut ratings = HashMap::<&str, Rating>::new();
+    for game in games {
+        for (name, &place) in game.players.iter().zip(&game.places) {
+            let rating = ratings.entry(name).or_insert_with(|| Rating {
+                name: name.clone(),
+                rating: START_RATING,
+                games: 0,
+                wins: 0,
+            });
+            rating.games += 1;
+            if place == 1 && game.places.iter().filter(|&&p| p == 1).count() == 1 {
+                rating.wins += 1;
+            }
+        }
+
+        let before = game
+            .players
+            .iter()
+            .map(|name| ratings[name.as_str()].rating)
+            .collect::<Vec<_>>();
+        let k = K_FACTOR / (game.players.len().max(2) - 1) as f64;
+        for (i, name) in game.players.iter().enumerate() {
+            let change = (0..game.players.len())
+                .filter(|&j| game.players[j] != *name)
+                .map(|j| {
+                    let expected = 1.0 / (1.0 + 10f64.powf((before[j] - before[i]) / 400.0));
+                    let score = match game.places[i].cmp(&game.places[j]) {
+                        std::cmp::Ordering::Less => 1.0,
+                        std::cmp::Ordering::Equal => 0.5,
+                        std::cmp::Ordering::Greater => 0.0,
+                    };
+                    k * (score - expected)
+                })
+                .sum::<f64>();
+            ratings.get_mut(name.as_str()).unwrap().rating += change;
+        }
+    }
+
+    let mut ratings = ratings.into_values().collect::<Vec<_>>();
+    ratings.sort_by(|a, b| b.rating.total_cmp(&a.rating).then(a.name.cmp(&b.name)));
+    ratings
+}
+
+impl Stats {
+    const FILE: &'static str = "stats.ron";
+
+    /// The games recorded so far, or none
+    pub fn load() -> Self {
+        let mut stats: Self = super::read_config(Self::FILE).unwrap_or_default();
+        stats.ratings = rate(&stats.games);
+        stats
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        super::write_config(Self::FILE, self)
+    }
+
+    /// Adds the finished game on the board, which took the given time to play
+    pub fn record(&mut self, board: &Board, duration: Duration) {
+        let outcome = match board.outcome() {
+            Some(outcome) => outcome,
+            None => return,
+        };
+        let mut standings = board.standings();
+        if let Some(winner) = outcome.winner() {
+            if let Some(i) = standings.iter().position(|s| s.turn == winner) {
+                let standing = standings.remove(i);
+                standings.insert(0, standing);
+            }
+        }
+
+        let (players, places) = standings
+            .iter()
+            .enumerate()
+            .map(|(i, standing)| {
+                let place = if outcome == Outcome::Draw { 1 } else { i + 1 };
+                (rated_name(board, standing.turn), place)
+            })
+            .unzip();
+        self.add(GameResult {
+            finished_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs()),
+            mode: board.mode,
+            players,
+            places,
+            moves: board.history.len(),
+            duration_secs: duration.as_secs_f64(),
+        });
+    }
+
+    fn add(&mut self, game: GameResult) {
+        self.games.push(game);
+        self.ratings = rate(&self.games);
+    }
+
+    pub fn games(&self) -> &[GameResult] {
+        &self.games
+    }
+
+    /// Elo ratings of everyone who played, best first (see [`rate`])
+    pub fn ratings(&self) -> &[Rating] {
+        &self.ratings
+    }
+
+    /// Every game on a line of its own, with the players in the order they placed
+    pub fn games_csv(&self) -> String {
+        let mut csv = "finished_at,mode,moves,duration_secs,players,places\n".to_string();
+        for game in &self.games {
+            csv += &format!(
+                "{},{},{},{:.0},{},{}\n",
+                game.finished_at,
+                csv_field(&game.mode.to_string()),
+                game.moves,
+                game.duration_secs,
+                csv_field(&game.players.join(";")),
+                csv_field(
+                    &game
+                        .places
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(";")
+                ),
+            );
+        }
+        csv
+    }
+
+    pub fn ratings_csv(&self) -> String {
+        let mut csv = "name,rating,games,wins\n".to_string();
+        for rating in &self.ratings {
+            csv += &format!(
+                "{},{:.0},{},{}\n",
+                csv_field(&rating.name),
+                rating.rating,
+                rating.games,
+                rating.wins
+            );
+        }
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(players: &[&str], places: &[usize]) -> GameResult {
+        GameResult {
+            finished_at: 0,
+            mode: Mode::Two,
+            players: players.iter().map(ToString::to_string).collect(),
+            places: places.to_vec(),
+            moves: 40,
+            duration_secs: 60.0,
+        }
+    }
+
+    fn rating<'a>(stats: &'a Stats, name: &str) -> &'a Rating {
+        stats.ratings().iter().find(|r| r.name == name).unwrap()
+    }
+
+    fn assert_rating(stats: &Stats, name: &str, expected: f64) {
+        let actual = rating(stats, name).rating;
+        assert!(
+            (actual - expected).abs() < 0.01,
+            "{name}: {actual} != {expected}"
+        );
+    }
+
+    #[test]
+    fn two_player_games_move_ratings_by_elo() {
+        let mut stats = Stats::default();
+        stats.add(game(&["Ann", "Bob"], &[1, 2]));
+        // evenly matched, so the winner takes half of the K factor
+        assert_rating(&stats, "Ann", 1516.0);
+        assert_rating(&stats, "Bob", 1484.0);
+
+        stats.add(game(&["Bob", "Ann"], &[2, 1]));
+        // Ann was expected to win 54.6% of the time, 1 / (1 + 10^(-32 / 400))
+        assert_rating(&stats, "Ann", 1530.53);
+        assert_rating(&stats, "Bob", 1469.47);
+        assert_eq!(
+            (rating(&stats, "Ann").games, rating(&stats, "Ann").wins),
+            (2, 2)
+        );
+
+        // a draw between equals changes nothing, and is nobody's win
+        stats.add(game(&["Cat", "Dan"], &[1, 1]));
+        assert_rating(&stats, "Cat", 1500.0);
+        assert_eq!(rating(&stats, "Dan").wins, 0);
+        assert_eq!(stats.ratings()[0].name, "Ann");
+    }
+
+    #[test]
+    fn games_of_several_players_are_scaled_pairwise() {
+        let mut stats = Stats::default();
+        stats.add(game(&["Ann", "Bob", "Cat"], &[1, 2, 3]));
+        // every pair of players plays for half of the K factor
+        assert_rating(&stats, "Ann", 1516.0);
+        assert_rating(&stats, "Bob", 1500.0);
+        assert_rating(&stats, "Cat", 1484.0);
+        let names = stats
+            .ratings()
+            .iter()
+            .map(|r| r.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["Ann", "Bob", "Cat"]);
+    }
+
+    #[test]
+    fn csv_fields_are_quoted_when_needed() {
+        assert_eq!(csv_field("Ann"), "Ann");
+        assert_eq!(csv_field("Ann, Bob"), "\"Ann, Bob\"");
+        assert_eq!(csv_field("the \"bot\""), "\"the \"\"bot\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+
+        let mut stats = Stats::default();
+        stats.add(game(&["Ann", "Bob, the bot"], &[1, 2]));
+        assert_eq!(
+            stats.games_csv(),
+            "finished_at,mode,moves,duration_secs,players,places\n0,Two,40,60,\"Ann;Bob, the bot\",1;2\n"
+        );
+        assert_eq!(
+            stats.ratings_csv(),
+            "name,rating,games,wins\nAnn,1516,1,1\n\"Bob, the bot\",1484,1,0\n"
+        );
+    }
+}
@@ -0,0 +1,135 @@
+//! Colors the board is drawn with, chosen by the player and remembered between sessions
+
+use nannou::{
+    color::{
+        encoding::{srgb, Linear},
+        Shade,
+    },
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumIter};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use super::player::Piece;
+
+pub type Color = rgb::Rgb<Linear<srgb::Srgb>>;
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug, EnumIter, Display, Serialize, Deserialize)]
+pub enum Palette {
+    #[default]
+    Classic,
+    /// Okabe and Ito's palette, distinguishable with the common kinds of colour blindness
+    #[strum(to_string = "Okabe-Ito (colour-blind safe)")]
+    OkabeIto,
+    /// Paul Tol's bright palette, also colour-blind safe
+    #[strum(to_string = "Tol bright (colour-blind safe)")]
+    TolBright,
+}
+
+impl Palette {
+    fn color(&self, piece: Piece) -> Srgb<u8> {
+        let [r, g, b] = match (self, piece) {
+            (_, Piece::None) => return DARKSLATEGRAY,
+            (Palette::Classic, piece) => {
+                return match piece {
+                    Piece::Player1 => DARKORCHID,
+                    Piece::Player2 => FUCHSIA,
+                    Piece::Player3 => YELLOWGREEN,
+                    Piece::Player4 => MAROON,
+                    Piece::Player5 => MIDNIGHTBLUE,
+                    _ => OLIVE,
+                }
+            }
+            (Palette::OkabeIto, piece) => match piece {
+                Piece::Player1 => [0xe6, 0x9f, 0x00],
+                Piece::Player2 => [0x56, 0xb4, 0xe9],
+                Piece::Player3 => [0x00, 0x9e, 0x73],
+                Piece::Player4 => [0xf0, 0xe4, 0x42],
+                Piece::Player5 => [0x00, 0x72, 0xb2],
+                _ => [0xd5, 0x5e, 0x00],
+            },
+            (Palette::TolBright, piece) => match piece {
+                Piece::Player1 => [0x44, 0x77, 0xaa],
+                Piece::Player2 => [0xee, 0x66, 0x77],
+                Piece::Player3 => [0x22, 0x88, 0x33],
+                Piece::Player4 => [0xcc, 0xbb, 0x44],
+                Piece::Player5 => [0x66, 0xcc, 0xee],
+                _ => [0xaa, 0x33, 0x77],
+            },
+        };
+        Srgb::new(r, g, b)
+    }
+}
+
+pub fn linear(color: Srgb<u8>) -> Color {
+    color.into_format::<f32>().into_linear()
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    pub palette: Palette,
+    pub dark: bool,
+    /// Draws a different glyph on each player's pieces, so they can be told apart without color
+    pub markers: bool,
+}
+
+impl Theme {
+    pub fn piece_color(&self, piece: Piece) -> Color {
+        if self.dark && piece.is_none() {
+            linear(Srgb::new(96, 96, 104))
+        } else {
+            linear(self.palette.color(piece))
+        }
+    }
+
+    pub fn background(&self) -> Color {
+        linear(if self.dark {
+            Srgb::new(24, 24, 28)
+        } else {
+            ANTIQUEWHITE
+        })
+    }
+
+    /// Fill of the central hexagon
+    pub fn board_color(&self) -> Color {
+        linear(if self.dark {
+            Srgb::new(48, 48, 54)
+        } else {
+            WHITE
+        })
+    }
+
+    /// Fill of the home triangle of pieces of the given color, and the stroke around it while its
+    /// owner is to move
+    pub fn triangle_colors(&self, color: Color) -> (Color, Color) {
+        if self.dark {
+            (color.darken(0.3), color.lighten(0.3))
+        } else {
+            (color.lighten(0.1), color.darken(0.2))
+        }
+    }
+
+    /// Color and opacity of the path being entered
+    pub fn highlight(&self) -> (Color, f32) {
+        let color = if self.dark { WHITE } else { BLACK };
+        (linear(color), 0.5)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Theme {
+    const FILE: &'static str = "theme.ron";
+
+    /// The theme saved last, or the default one
+    pub fn load() -> Self {
+        super::read_config(Self::FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        super::write_config(Self::FILE, self)
+    }
+}
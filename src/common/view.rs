@@ -0,0 +1,267 @@
+//! Where the board is shown in the window: how it is turned, zoomed and panned
+
+use std::{collections::HashMap, time::Duration};
+
+use nannou::{
+    event::{MouseScrollDelta, TouchEvent, TouchPhase},
+    prelude::*,
+};
+
+use super::{board::Board, player::Turn};
+
+/// How quickly the board turns towards its target angle, in the fraction of the remaining angle
+/// covered per second (roughly)
+const ROTATION_SPEED: f32 = 8.0;
+const MIN_ZOOM: f32 = 0.5;
+const MAX_ZOOM: f32 = 8.0;
+/// Zoom factor of one notch of the mouse wheel
+const WHEEL_ZOOM: f32 = 1.15;
+
+pub struct View {
+    /// Turns the board so that the home of the player to move is at the bottom
+    pub follow_turn: bool,
+    /// Writes the name of each cell on it, and describes the cell under the cursor
+    pub labels: bool,
+    /// The angle the board is currently drawn turned by, counterclockwise
+    rotation: f32,
+    zoom: f32,
+    /// Offset of the center of the board from the center of the window, in the units the whole
+    /// board fits into at zoom 1
+    pan: Vec2,
+    /// Where the cursor was last while the board is being dragged around
+    drag: Option<Point2>,
+    /// Fingers on the screen, by id
+    touches: HashMap<u64, Point2>,
+}
+
+impl Default for View {
+    fn default() -> Self {
+        Self {
+            follow_turn: false,
+            labels: false,
+            rotation: 0.0,
+            zoom: 1.0,
+            pan: Vec2::ZERO,
+            drag: None,
+            touches: HashMap::new(),
+        }
+    }
+}
+
+impl View {
+    pub fn rotation(&self) -> f32 {
+        self.rotation
+    }
+
+    /// The size of a pixel in board coordinates
+    pub fn pixel(&self, scale: f32) -> f32 {
+        1.0 / (scale * self.zoom)
+    }
+
+    /// The angle the board should end up turned by
+    fn target_rotation(&self, board: &Board) -> f32 {
+        if !self.follow_turn || board.turn == Turn::None {
+            return 0.0;
+        }
+        Board::home_angle(board.turn, board.mode)
+            .map(|angle| -f32::FRAC_PI_2() - angle)
+            .unwrap_or(0.0)
+    }
+
+    /// Turns the board a bit further towards where it should be, the short way around
+    pub fn update(&mut self, board: &Board, elapsed: Duration) {
+        let remaining = (self.target_rotation(board) - self.rotation + f32::PI())
+            .rem_euclid(2.0 * f32::PI())
+            - f32::PI();
+        let step = 1.0 - (-ROTATION_SPEED * elapsed.as_secs_f32()).exp();
+        self.rotation = if remaining.abs() < 1e-3 {
+            self.rotation + remaining
+        } else {
+            self.rotation + remaining * step
+        }
+        .rem_euclid(2.0 * f32::PI());
+    }
+
+    /// Places drawings given in board coordinates where they belong in the window. `scale` is the
+    /// size the whole board fits into at zoom 1.
+    pub fn transform(&self, draw: &Draw, scale: f32) -> Draw {
+        draw.scale_axes(Vec3::splat(scale))
+            .translate(self.pan.extend(0.0))
+            .scale(self.zoom)
+            .rotate(self.rotation)
+    }
+
+    /// The inverse of [`View::transform`], taking a position in the window (say, of the cursor)
+    /// to board coordinates
+    pub fn to_board(&self, window: Point2, scale: f32) -> Point2 {
+        ((window / scale - self.pan) / self.zoom).rotate(-self.rotation)
+    }
+
+    /// Zooms in by the given factor (or out, below 1) while keeping the point under the given
+    /// window position in place
+    pub fn zoom_at(&mut self, window: Point2, scale: f32, factor: f32) {
+        let zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        let anchor = window / scale;
+        self.pan = anchor - (anchor - self.pan) * zoom / self.zoom;
+        self.zoom = zoom;
+    }
+
+    pub fn wheel(&mut self, delta: MouseScrollDelta, window: Point2, scale: f32) {
+        let notches = match delta {
+            MouseScrollDelta::LineDelta(_, y) => y,
+            MouseScrollDelta::PixelDelta(position) => position.y as f32 / 50.0,
+        };
+        self.zoom_at(window, scale, WHEEL_ZOOM.powf(notches));
+    }
+
+    pub fn start_drag(&mut self, window: Point2) {
+        self.drag = Some(window);
+    }
+
+    pub fn end_drag(&mut self) {
+        self.drag = None;
+    }
+
+    /// Moves the board along with the cursor while it is being dragged
+    pub fn drag_to(&mut self, window: Point2, scale: f32) {
+        if let Some(last) = self.drag {
+            self.pan += (window - last) / scale;
+            self.drag = Some(window);
+        }
+    }
+
+    /// Pans with one finger, and pans and zooms with two by pinching
+    pub fn touch(&mut self, touch: TouchEvent, scale: f32) {
+        match touch.phase {
+            TouchPhase::Started => {
+                self.touches.insert(touch.id, touch.position);
+            }
+            TouchPhase::Moved => {
+                let last = match self.touches.insert(touch.id, touch.position) {
+                    Some(last) => last,
+                    None => return,
+                };
+                let others = self
+                    .touches
+                    .iter()
+                    .filter(|&(&id, _)| id != touch.id)
+                    .map(|(_, &position)| position)
+                    .collect::<Vec<_>>();
+                match others[..] {
+                    [] => self.pan += (touch.position - last) / scale,
+                    [other] => {
+                        let old_center = (last + other) / 2.0;
+                        let new_center = (touch.position + other) / 2.0;
+                        self.pan += (new_center - old_center) / scale;
+                        let factor = touch.position.distance(other) / last.distance(other).max(1.0);
+                        self.zoom_at(new_center, scale, factor);
+                    }
+                    _ => (),
+                }
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.touches.remove(&touch.id);
+            }
+        }
+    }
+
+    /// Fits the whole board into the window again
+    pub fn reset(&mut self) {
+        self.zoom = 1.0;
+        self.pan = Vec2::ZERO;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nannou::{draw::DrawCommand, state::Mouse};
+    use strum::IntoEnumIterator;
+
+    use super::super::player::Mode;
+    use super::*;
+
+    const SCALE: f32 = 300.0;
+
+    /// Where the drawings `transform` places end up in the window
+    fn to_window(view: &View, point: Point2) -> Point2 {
+        let draw = Draw::new();
+        view.transform(&draw, SCALE).ellipse();
+        let transform = draw
+            .drain_commands()
+            .find_map(|command| match command {
+                DrawCommand::Context(context) => Some(context.transform),
+                _ => None,
+            })
+            .unwrap();
+        transform.transform_point3(point.extend(0.0)).truncate()
+    }
+
+    #[test]
+    fn cells_are_found_on_the_turned_board() {
+        for mode in Mode::iter() {
+            let mut board = Board::default();
+            board.reset(mode);
+            let mut view = View {
+                follow_turn: true,
+                ..View::default()
+            };
+            for turn in mode.turns() {
+                board.turn = turn;
+                view.update(&board, Duration::from_secs(10));
+                view.update(&board, Duration::from_secs(10));
+                // the home of the player to move points straight down
+                let angle = Board::home_angle(turn, mode).unwrap() + view.rotation();
+                let down = 1.5 * f32::PI();
+                assert!((angle.rem_euclid(2.0 * f32::PI()) - down).abs() < 1e-3);
+
+                for (position, _) in board.pieces() {
+                    let window = to_window(&view, Board::physical_position(&position));
+                    let mouse = Mouse {
+                        x: window.x,
+                        y: window.y,
+                        ..Mouse::new()
+                    };
+                    assert_eq!(board.position_of(&mouse, &view, SCALE), Some(position));
+                }
+            }
+        }
+    }
+
+    fn assert_near(a: Point2, b: Point2) {
+        assert!(a.distance(b) < 1e-4, "{a} is not {b}");
+    }
+
+    #[test]
+    fn zooming_and_panning_move_the_board_under_the_cursor() {
+        let mut view = View::default();
+        let cursor = pt2(100.0, 50.0);
+        let under_cursor = view.to_board(cursor, SCALE);
+        view.zoom_at(cursor, SCALE, 2.0);
+        assert_near(view.to_board(cursor, SCALE), under_cursor);
+        assert_near(view.to_board(Vec2::ZERO, SCALE), under_cursor / 2.0);
+
+        // dragging moves the board along with the cursor
+        view.start_drag(cursor);
+        view.drag_to(cursor + pt2(30.0, -60.0), SCALE);
+        view.end_drag();
+        view.drag_to(pt2(-500.0, 0.0), SCALE);
+        assert_near(
+            view.to_board(cursor + pt2(30.0, -60.0), SCALE),
+            under_cursor,
+        );
+
+        // `to_board` undoes `transform`, however the board is turned
+        view.rotation = 1.0;
+        for point in [Vec2::ZERO, pt2(0.3, -0.2), pt2(-0.7, 0.5)] {
+            assert_near(view.to_board(to_window(&view, point), SCALE), point);
+        }
+
+        view.zoom_at(cursor, SCALE, 100.0);
+        assert_eq!(view.zoom, MAX_ZOOM);
+        view.reset();
+        assert_near(
+            to_window(&view, pt2(0.5, 0.0)),
+            pt2(0.5, 0.0).rotate(1.0) * SCALE,
+        );
+    }
+}
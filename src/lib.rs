@@ -1,11 +1,23 @@
-#![cfg(target_arch = "wasm32")]
+//! The game and everything around it. The binaries build on the modules exported here: the
+//! window on [`model`], [`events`] and [`update`], the command line tools on the rest. On the web,
+//! `main_web` runs the game in the page.
 
+#[cfg(target_arch = "wasm32")]
 use async_std::task::block_on;
+#[cfg(target_arch = "wasm32")]
 use nannou::{prelude::*, wgpu::Backends};
+#[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::wasm_bindgen;
 
 mod common;
 
+pub use common::{
+    ai, analysis, board, book, clock, events, model, player, rules, search, theme, update, view,
+};
+#[cfg(not(target_arch = "wasm32"))]
+pub use common::{engine, stats};
+
+#[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
 pub async fn main_web() {
     #[cfg(debug_assertions)]
@@ -1,10 +1,104 @@
 #![cfg_attr(target_arch = "wasm32", allow(unused))]
 
-use common::{events, model, update};
+use ccheckers_wasm::{
+    analysis::Analysis,
+    board::{endgame::RaceBudget, puzzle::Puzzle, Board},
+    events, model,
+    theme::Theme,
+    update,
+};
 
-mod common;
+fn load_game(game: &str) -> Result<Board, String> {
+    let data = std::fs::read_to_string(game).map_err(|e| format!("could not read {game}: {e}"))?;
+    ron::from_str(&data).map_err(|e| format!("could not load {game}: {e}"))
+}
+
+/// `ccheckers --export-<svg|png|gif> <saved game> <out file>` renders a saved game without opening
+/// a window. GIFs replay every move of the game.
+fn export(format: &str, game: &str, out: &str) -> Result<(), String> {
+    let board = load_game(game)?;
+    let theme = Theme::default();
+    let bytes = match format {
+        "svg" => Ok(board.to_svg(800, &theme).into_bytes()),
+        "png" => board.to_png(800, &theme),
+        "gif" => board.replay_gif(400, 700, &theme),
+        _ => return Err(format!("unknown export format {format}")),
+    }
+    .map_err(|e| format!("could not render {game}: {e}"))?;
+    std::fs::write(out, bytes).map_err(|e| format!("could not write {out}: {e}"))
+}
+
+/// `ccheckers --check-puzzles <puzzle file>` makes sure every puzzle of the file can be solved
+/// in as many moves as it allows, listing what is wrong with the others
+fn check_puzzles(file: &str) -> Result<(), String> {
+    let data = std::fs::read_to_string(file).map_err(|e| format!("could not read {file}: {e}"))?;
+    let puzzles: Vec<Puzzle> =
+        ron::from_str(&data).map_err(|e| format!("could not load {file}: {e}"))?;
+    let mut broken = 0;
+    for puzzle in &puzzles {
+        match puzzle.verify() {
+            Ok(moves) => println!("{}: solvable in {moves} moves", puzzle.name),
+            Err(e) => {
+                println!("{}: {e}", puzzle.name);
+                broken += 1;
+            }
+        }
+    }
+    match broken {
+        0 => Ok(()),
+        _ => Err(format!("{broken} of {} puzzles are broken", puzzles.len())),
+    }
+}
+
+/// `ccheckers --race <saved game>` works out how many moves each player needs to finish once the
+/// pieces of a two player game have passed each other
+fn solve_race(game: &str) -> Result<(), String> {
+    let board = load_game(game)?;
+    if !board.is_race() {
+        return Err(format!("the pieces in {game} have not passed each other"));
+    }
+    let race = board
+        .race(RaceBudget::EXHAUSTIVE)
+        .ok_or_else(|| "the race is too long to solve".to_string())?;
+    println!("{}", race.summary(&board));
+    Ok(())
+}
+
+/// `ccheckers --analyse <saved game>` estimates how many moves every player needed after each
+/// move of the game, and lists the blunders
+fn analyse(game: &str) -> Result<(), String> {
+    let board = load_game(game)?;
+    print!("{}", Analysis::of(&board).text(&board));
+    Ok(())
+}
 
 fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if let [flag, file] = &args[..] {
+        let result = match flag.as_str() {
+            "--check-puzzles" => Some(check_puzzles(file)),
+            "--race" => Some(solve_race(file)),
+            "--analyse" => Some(analyse(file)),
+            _ => None,
+        };
+        if let Some(result) = result {
+            if let Err(e) = result {
+                eprintln!("error: {e}");
+                std::process::exit(1);
+            }
+            return;
+        }
+    }
+    if let [flag, game, out] = &args[..] {
+        if let Some(format) = flag.strip_prefix("--export-") {
+            if let Err(e) = export(format, game, out) {
+                eprintln!("error: {e}");
+                std::process::exit(1);
+            }
+            return;
+        }
+    }
+
     #[cfg(not(target_arch = "wasm32"))]
     nannou::app(model).event(events).update(update).run()
 }
@@ -0,0 +1,278 @@
+#![cfg_attr(target_arch = "wasm32", allow(unused))]
+
+//! Plays bots against each other without opening a window.
+//!
+//! Usage: `ccheckers_tournament [--games N] [--seed S] [--modes two,three,six]
+//! [--seats greedy,random,search:3,..] [--rotate] [--max-moves N] [--records FILE] [--anti-spoiling]
+//! [--no-play-on] [--home-limit PIECES:MOVES] [--repetition N] [--move-limit N]
+//! [--limit-result draw|progress] [--book FILE]`
+//!
+//! Seats are handed out in turn order, repeating the list if it is shorter than the number of
+//! players. With `--rotate`, the list is shifted by one seat every game so that no bot always
+//! moves first. A seat can also be `engine:<command>` to play an external engine, or
+//! `network:<host:port>` to play one at another machine. With `--book`, every seat but those plays
+//! from the opening book while it knows the position.
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, Write},
+    sync::Arc,
+    time::Instant,
+};
+
+use ccheckers_wasm::{
+    ai::{self, BookBot, Bot},
+    board::{Board, Move, Outcome},
+    book::Book,
+    player::Mode,
+    rules::{HomeLimit, LimitResult, Rules},
+};
+use serde::Serialize;
+use strum::IntoEnumIterator;
+
+struct Config {
+    games: usize,
+    seed: u64,
+    modes: Vec<Mode>,
+    seats: Vec<String>,
+    rotate: bool,
+    max_moves: usize,
+    records: Option<String>,
+    rules: Rules,
+    book: Option<Arc<Book>>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            games: 100,
+            seed: 0,
+            modes: Mode::iter().collect(),
+            seats: vec!["greedy".to_string()],
+            rotate: false,
+            max_moves: 2000,
+            records: None,
+            rules: Rules::default(),
+            book: None,
+        }
+    }
+}
+
+fn parse_mode(s: &str) -> Result<Mode, String> {
+    Mode::iter()
+        .find(|mode| mode.to_string().eq_ignore_ascii_case(s))
+        .ok_or_else(|| format!("unknown mode {s}"))
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Config, String> {
+    let mut config = Config::default();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value for {arg}"))
+        };
+        match arg.as_str() {
+            "--games" => config.games = value()?.parse().map_err(|e| format!("{e}"))?,
+            "--seed" => config.seed = value()?.parse().map_err(|e| format!("{e}"))?,
+            "--max-moves" => config.max_moves = value()?.parse().map_err(|e| format!("{e}"))?,
+            "--modes" => {
+                config.modes = value()?
+                    .split(',')
+                    .map(parse_mode)
+                    .collect::<Result<_, _>>()?
+            }
+            "--seats" => config.seats = value()?.split(',').map(str::to_string).collect(),
+            "--rotate" => config.rotate = true,
+            "--records" => config.records = Some(value()?),
+            "--book" => config.book = Some(Arc::new(Book::load(&value()?)?)),
+            "--anti-spoiling" => config.rules.anti_spoiling = true,
+            "--no-play-on" => config.rules.play_on = false,
+            "--repetition" => {
+                config.rules.repetition = Some(value()?.parse().map_err(|e| format!("{e}"))?)
+            }
+            "--move-limit" => {
+                config.rules.move_limit = Some(value()?.parse().map_err(|e| format!("{e}"))?)
+            }
+            "--limit-result" => {
+                let value = value()?;
+                config.rules.limit_result = LimitResult::iter()
+                    .find(|result| result.to_string().eq_ignore_ascii_case(&value))
+                    .ok_or_else(|| format!("unknown limit result {value}"))?;
+            }
+            "--home-limit" => {
+                let value = value()?;
+                let (pieces, moves) = value
+                    .split_once(':')
+                    .ok_or_else(|| format!("expected PIECES:MOVES, got {value}"))?;
+                config.rules.home_limit = Some(HomeLimit {
+                    max_pieces: pieces.parse().map_err(|e| format!("{e}"))?,
+                    after_moves: moves.parse().map_err(|e| format!("{e}"))?,
+                });
+            }
+            _ => return Err(format!("unknown argument {arg}")),
+        }
+    }
+
+    if config.seats.is_empty() {
+        return Err("at least one seat must be given".to_string());
+    }
+    if let Some(bad) = config.seats.iter().find(|name| !ai::is_bot_name(name)) {
+        return Err(format!("unknown bot {bad}"));
+    }
+
+    Ok(config)
+}
+
+#[derive(Serialize)]
+struct GameRecord {
+    mode: Mode,
+    seed: u64,
+    seats: Vec<String>,
+    outcome: Option<Outcome>,
+    /// Seats in the order they finished in
+    placings: Vec<String>,
+    moves: usize,
+    duration_ms: u64,
+    /// Every move of the game, e.g. to build an opening book from
+    history: Vec<Move>,
+}
+
+/// Plays a single game to completion, or until the seats have had as many turns as the move limit,
+/// counting passed turns too. Fails if a bot cannot be started.
+fn play_game(
+    config: &Config,
+    mode: Mode,
+    seats: Vec<String>,
+    seed: u64,
+) -> Result<GameRecord, String> {
+    let mut bots = seats
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let seed = seed.wrapping_add(i as u64);
+            let bot = ai::bot_from_name(name, seed).map_err(|e| format!("{name}: {e}"))?;
+            Ok(match &config.book {
+                Some(book) if !name.starts_with("engine:") && !name.starts_with("network:") => {
+                    Box::new(BookBot::new(book.clone(), seed, bot)) as Box<dyn Bot>
+                }
+                _ => bot,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let mut board = Board::default();
+    board.rules = config.rules;
+    board.reset(mode);
+
+    let start = Instant::now();
+    let (mut moves, mut turns) = (0, 0);
+    // seats that never come up with a legal move would otherwise keep the game going forever
+    while board.outcome().is_none() && turns < config.max_moves {
+        turns += 1;
+        let seat = mode.turns().position(|turn| turn == board.turn).unwrap();
+        match bots[seat].choose(&board) {
+            Some(path) if board.play_path(&path) => moves += 1,
+            // a seat without a (legal) move forfeits its turn
+            _ => board.skip_turn(),
+        }
+        if let Some(problem) = bots[seat].take_problem() {
+            eprintln!("{}: {problem}", seats[seat]);
+        }
+    }
+
+    Ok(GameRecord {
+        mode,
+        seed,
+        placings: board
+            .placings
+            .iter()
+            .filter_map(|&turn| mode.turns().position(|other| other == turn))
+            .map(|i| seats[i].clone())
+            .collect(),
+        seats,
+        outcome: board.outcome(),
+        moves,
+        duration_ms: start.elapsed().as_millis() as u64,
+        history: board.history,
+    })
+}
+
+#[derive(Default)]
+struct Totals {
+    games: usize,
+    wins: usize,
+}
+
+fn run(config: Config) -> io::Result<()> {
+    let mut records: Box<dyn Write> = match &config.records {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::sink()),
+    };
+
+    for &mode in &config.modes {
+        let mut per_bot: HashMap<String, Totals> = HashMap::new();
+        let (mut decided, mut drawn, mut total_moves, mut total_ms) = (0, 0, 0, 0);
+
+        for game in 0..config.games {
+            let offset = if config.rotate { game } else { 0 };
+            let seats = (0..mode.players())
+                .map(|i| config.seats[(i + offset) % config.seats.len()].clone())
+                .collect();
+            let seed = config.seed.wrapping_add(game as u64);
+            let record = play_game(&config, mode, seats, seed).map_err(io::Error::other)?;
+
+            for (turn, name) in mode.turns().zip(&record.seats) {
+                let totals = per_bot.entry(name.clone()).or_default();
+                totals.games += 1;
+                if record.outcome.and_then(|outcome| outcome.winner()) == Some(turn) {
+                    totals.wins += 1;
+                }
+            }
+            decided += record.outcome.is_some() as usize;
+            drawn += (record.outcome == Some(Outcome::Draw)) as usize;
+            total_moves += record.moves;
+            total_ms += record.duration_ms;
+
+            writeln!(records, "{}", ron::to_string(&record).unwrap())?;
+        }
+
+        let games = config.games.max(1);
+        println!("== {mode} players, {} games ==", config.games);
+        println!(
+            "finished: {decided} ({drawn} drawn), average moves: {:.1} ({:.1} per player), average length: {:.1}ms",
+            total_moves as f64 / games as f64,
+            total_moves as f64 / (games * mode.players()) as f64,
+            total_ms as f64 / games as f64,
+        );
+        let mut names = per_bot.keys().cloned().collect::<Vec<_>>();
+        names.sort();
+        for name in names {
+            let totals = &per_bot[&name];
+            println!(
+                "{name:>12}: {:>5} wins in {:>5} seats ({:.1}%)",
+                totals.wins,
+                totals.games,
+                100.0 * totals.wins as f64 / totals.games as f64
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn main() {
+    #[cfg(not(target_arch = "wasm32"))]
+    match parse_args(std::env::args().skip(1)) {
+        Ok(config) => {
+            if let Err(e) = run(config) {
+                eprintln!("error: {e}");
+                std::process::exit(1);
+            }
+        }
+        Err(e) => {
+            eprintln!("error: {e}");
+            std::process::exit(2);
+        }
+    }
+}